@@ -2,8 +2,9 @@ extern crate log;
 extern crate pretty_env_logger;
 
 use keyhunter::{
+    pipeline::Pipeline,
     report::{Reporter, ReporterBuilder},
-    ApiKeyCollector, ApiKeyError, ApiKeyMessage, Config, ScriptMessage, WebsiteWalkBuilder,
+    ApiKeyError, ApiKeyMessage, Config,
 };
 use log::{error, info};
 use miette::{miette, Context as _, Error, IntoDiagnostic as _, Result};
@@ -13,13 +14,10 @@ use std::{
     fs::{self, File},
     io::{BufWriter, Write},
     path::{Path, PathBuf},
-    sync::{mpsc, Arc, RwLock},
-    thread,
+    sync::Arc,
     time::Duration,
 };
 
-type SyncReporter<R> = Arc<RwLock<Reporter<R>>>;
-
 fn yc_path() -> Result<PathBuf> {
     let file_path = PathBuf::from(file!()).canonicalize().into_diagnostic()?;
     let root_dir = file_path
@@ -102,111 +100,56 @@ fn main() -> Result<()> {
 
     let config = Arc::new(Config::gitleaks());
 
-    let reporter: SyncReporter<_> = Arc::new(RwLock::new(
-        ReporterBuilder::default().with_redacted(true).graphical(),
-    ));
+    let reporter: Reporter<_> = ReporterBuilder::default().with_redacted(true).graphical();
 
     let yc_sites_raw = yc_file().unwrap();
     let yc_reader = csv::Reader::from_reader(yc_sites_raw.as_bytes());
     let mut key_writer = outfile()?;
 
-    let (key_sender, key_receiver) = mpsc::channel::<ApiKeyMessage>();
-
-    // keys will come in here
-    thread::spawn(move || {
-        while let Ok(message) = key_receiver.recv() {
-            match message {
-                ApiKeyMessage::Keys(api_keys) => {
-                    reporter.write().unwrap().report_keys(&api_keys).unwrap();
-                    for api_key in api_keys {
-                        let url = api_key.url.clone();
-                        write_keys(&mut key_writer, api_key)
-                            .context(format!("Failed to write api keys for script {}", &url))
-                            .unwrap();
-                    }
-                    let _ = key_writer.flush();
-                }
-                ApiKeyMessage::RecoverableFailure(e) => {
-                    println!("{:?}", e)
-                }
-                ApiKeyMessage::Stop => {
-                    break;
-                }
-                // ignore statistics-related messages
-                _ => {}
-            }
-        }
-        let _ = key_writer.flush();
-    });
+    let urls: Vec<String> = yc_reader
+        .into_records()
+        .flatten()
+        .filter(|record| !record[0].eq_ignore_ascii_case("million"))
+        .map(|record| record[1].to_string())
+        .collect();
 
-    let walk_builder = WebsiteWalkBuilder::new()
+    let pipeline = Pipeline::builder()
+        .with_config(config)
         .with_max_walks(max_walks)
         .with_random_ua(true)
-        .with_cookie_jar(true)
-        .with_shared_cache(true)
-        .with_close_channel(false)
         .with_timeout(Duration::from_secs(15))
-        .with_timeout_connect(Duration::from_secs(2));
-    yc_reader
-        .into_records()
-        // .par_bridge()
-        .flatten()
-        .for_each(|record| {
-            let name = &record[0];
-            let url = record[1].to_string();
-            if name.eq_ignore_ascii_case("million") {
-                return;
-            }
+        .with_timeout_connect(Duration::from_secs(2))
+        .build();
+    let (key_receiver, handle) = pipeline.run(urls);
 
-            info!(target: "keyhunter::main", "Scraping keys for site {name}...");
-            let (tx_scripts, rx_scripts) = mpsc::channel::<ScriptMessage>();
-            let walker = walk_builder.build(tx_scripts.clone());
-            let collector = ApiKeyCollector::new(config.clone(), rx_scripts, key_sender.clone());
-
-            // Visit pages in the target site, sending found script urls over the
-            // script channel
-            let moved_url = url.clone();
-            let walk_handle = thread::spawn(move || {
-                let result = walker.walk(&moved_url);
-                if result.is_err() {
-                    error!(target: "keyhunter::main",
-                        "failed to create walker: {}",
-                        result.as_ref().unwrap_err()
-                    );
-                    tx_scripts
-                        .send(ScriptMessage::Done)
-                        .into_diagnostic()
-                        .context("Failed to send stop signal over script channel")
+    // keys will come in here
+    for message in key_receiver {
+        match message {
+            ApiKeyMessage::Keys(api_keys) => {
+                reporter.report_keys(&api_keys).unwrap();
+                for api_key in api_keys {
+                    let url = api_key.url.clone();
+                    write_keys(&mut key_writer, api_key)
+                        .context(format!("Failed to write api keys for script {}", &url))
                         .unwrap();
                 }
-                result
-            });
-
-            // receive scripts from website walker. Download them parse them,
-            // and look for API keys
-            let collector_handle = thread::spawn(move || collector.collect());
-
-            collector_handle
-                .join()
-                .expect("ApiKeyCollector thread should have joined successfully");
-            let walk_result = walk_handle
-                .join()
-                .expect("WebsiteWalker thread should have joined successfully");
-            match walk_result {
-                Ok(_) => {
-                    info!(target: "keyhunter::main", "Done scraping for {name}");
-                }
-                Err(e) => {
-                    error!(target: "keyhunter::main", "[run] Failed to scrape for '{url}': {e}");
-                }
+                let _ = key_writer.flush();
+            }
+            ApiKeyMessage::RecoverableFailure { error, .. } => {
+                println!("{:?}", error)
             }
-        });
+            ApiKeyMessage::Stop => {
+                break;
+            }
+            // ignore statistics-related messages
+            _ => {}
+        }
+    }
+    let _ = key_writer.flush();
 
-    key_sender
-        .send(ApiKeyMessage::Stop)
-        .into_diagnostic()
-        .context("Failed to close API key channel")
-        .unwrap();
+    for e in handle.join().unwrap() {
+        error!(target: "keyhunter::main", "[run] Failed to scrape site: {e:?}");
+    }
 
     info!("Scraping completed");
 
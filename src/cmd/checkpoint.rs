@@ -0,0 +1,131 @@
+/// Copyright © 2024 Don Isaac
+///
+/// This file is part of KeyHunter.
+///
+/// KeyHunter is free software: you can redistribute it and/or modify it
+/// under the terms of the GNU General Public License as published by the Free
+/// Software Foundation, either version 3 of the License, or (at your option)
+/// any later version.
+///
+/// KeyHunter is distributed in the hope that it will be useful, but WITHOUT
+/// ANY WARRANTY; without even the implied warranty of MERCHANTABILITY or
+/// FITNESS FOR A PARTICULAR PURPOSE. See the GNU General Public License for
+/// more details.
+///
+/// You should have received a copy of the GNU General Public License along with
+/// KeyHunter. If not, see <https://www.gnu.org/licenses/>.
+use std::{
+    collections::HashSet,
+    fs::{self, File, OpenOptions},
+    io::Write as _,
+    path::Path,
+    sync::Mutex,
+};
+
+use keyhunter::pipeline::CompletedSites;
+use miette::{Context as _, IntoDiagnostic as _, Result};
+
+/// Records which sites have been fully scanned, so that an interrupted
+/// multi-site scan can be resumed.
+///
+/// The checkpoint file lists one completed entrypoint URL per line. URLs are
+/// appended as soon as a site finishes, so progress survives crashes.
+#[derive(Debug)]
+pub struct Checkpoint {
+    completed: HashSet<String>,
+    file: Mutex<File>,
+}
+
+impl Checkpoint {
+    /// Open a checkpoint file, creating it if it does not exist.
+    ///
+    /// When `resume` is `true`, sites recorded in the file are treated as
+    /// completed. Otherwise, the file is cleared.
+    pub fn open<P: AsRef<Path>>(path: P, resume: bool) -> Result<Self> {
+        let path = path.as_ref();
+        let completed = if resume && path.exists() {
+            fs::read_to_string(path)
+                .into_diagnostic()
+                .with_context(|| format!("Failed to read checkpoint {}", path.display()))?
+                .lines()
+                .map(str::trim)
+                .filter(|line| !line.is_empty())
+                .map(str::to_string)
+                .collect()
+        } else {
+            HashSet::new()
+        };
+
+        let file = OpenOptions::new()
+            .create(true)
+            .append(resume)
+            .write(true)
+            .truncate(!resume)
+            .open(path)
+            .into_diagnostic()
+            .with_context(|| format!("Failed to open checkpoint {}", path.display()))?;
+
+        Ok(Self {
+            completed,
+            file: Mutex::new(file),
+        })
+    }
+
+    /// Returns `true` if `url` was completed by a previous run.
+    pub fn is_completed(&self, url: &str) -> bool {
+        self.completed.contains(url)
+    }
+
+    /// Record that `url` has been fully scanned.
+    pub fn mark_completed(&self, url: &str) -> Result<()> {
+        let mut file = self.file.lock().unwrap();
+        writeln!(file, "{url}")
+            .and_then(|()| file.flush())
+            .into_diagnostic()
+            .context("Failed to update checkpoint")
+    }
+}
+
+impl CompletedSites for Checkpoint {
+    fn is_completed(&self, url: &str) -> bool {
+        Checkpoint::is_completed(self, url)
+    }
+
+    fn mark_completed(&self, url: &str) -> Result<()> {
+        Checkpoint::mark_completed(self, url)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_checkpoint() {
+        let path = std::env::temp_dir().join(format!(
+            "keyhunter-checkpoint-{}.txt",
+            rand::random::<u32>()
+        ));
+
+        let checkpoint = Checkpoint::open(&path, true).unwrap();
+        assert!(!checkpoint.is_completed("https://a.com"));
+        checkpoint.mark_completed("https://a.com").unwrap();
+        drop(checkpoint);
+
+        let checkpoint = Checkpoint::open(&path, true).unwrap();
+        assert!(checkpoint.is_completed("https://a.com"));
+        checkpoint.mark_completed("https://b.com").unwrap();
+        drop(checkpoint);
+        assert_eq!(
+            fs::read_to_string(&path).unwrap(),
+            "https://a.com\nhttps://b.com\n"
+        );
+
+        // not resuming starts over
+        let checkpoint = Checkpoint::open(&path, false).unwrap();
+        assert!(!checkpoint.is_completed("https://a.com"));
+        assert_eq!(fs::read_to_string(&path).unwrap(), "");
+
+        fs::remove_file(&path).unwrap();
+    }
+}
@@ -14,10 +14,11 @@
 ///
 /// You should have received a copy of the GNU General Public License along with
 /// KeyHunter. If not, see <https://www.gnu.org/licenses/>.
-use std::path::PathBuf;
+use std::{path::PathBuf, time::Duration};
 
 use clap::{ArgAction, Parser, ValueHint};
 use clap_verbosity_flag::Verbosity;
+use keyhunter::ApiKeyCollector;
 use miette::{self, IntoDiagnostic as _, Result};
 use serde::{Deserialize, Serialize};
 
@@ -25,13 +26,14 @@ use serde::{Deserialize, Serialize};
 #[command(author, version, about, long_about = None)]
 #[command(propagate_version = true)]
 pub struct Cli {
-    /// The URL to start crawling from.
+    /// The URLs to start crawling from. Each URL is crawled as a separate
+    /// site.
     ///
     /// You omit the protocol (e.g. `http://`, `https://`) and KeyHunter will
     /// automatically use `https://`.
-    #[arg(name = "url")]
+    #[arg(name = "url", required = true)]
     #[arg(value_hint = ValueHint::Url)]
-    entrypoint: String,
+    entrypoints: Vec<String>,
 
     /// Path to a file where the output will be written.
     ///
@@ -80,6 +82,167 @@ pub struct Cli {
     #[arg(long)]
     #[arg(short = 'H', value_parser = parse_header_and_value::<String, String>)]
     header: Vec<(String, String)>,
+
+    /// Accept invalid TLS certificates, e.g. self-signed ones used by
+    /// staging sites.
+    ///
+    /// Connections can be intercepted by anyone between you and the site, so
+    /// only use this for sites you control.
+    #[arg(long, short = 'k')]
+    insecure: bool,
+
+    /// Only list the scripts that would be checked, without downloading them
+    /// or checking them for API keys.
+    ///
+    /// Useful for debugging what a crawl covers.
+    #[arg(long)]
+    list_scripts: bool,
+
+    /// Also check same-origin JSON files referenced by scripts for API keys.
+    ///
+    /// Single-page apps often load secrets from files like `/config.json` or
+    /// `/env.json` at runtime. When set, `.json` URLs found in scripts are
+    /// downloaded and checked as well.
+    #[arg(long)]
+    scan_json: bool,
+
+    /// Also check each site for publicly served config files, like `/.env`
+    /// or `/config.yaml`.
+    ///
+    /// Makes a request for each well-known config path on every site. Use
+    /// `--probe-path` to check other paths.
+    #[arg(long)]
+    probe_config: bool,
+
+    /// Check this config file path on each site, e.g. `--probe-path
+    /// /settings.yml`. May be repeated. Implies `--probe-config`.
+    #[arg(long, value_name = "PATH")]
+    probe_path: Vec<String>,
+
+    /// Path to a gitleaks TOML config file with the rules to scan for.
+    ///
+    /// By default, the rules from gitleaks' own config are used.
+    #[arg(long, short = 'c', value_name = "FILE")]
+    #[arg(value_hint = ValueHint::FilePath)]
+    config: Option<PathBuf>,
+
+    /// Only scan for rules whose IDs match one of these globs.
+    ///
+    /// Takes a comma-separated list, e.g. `--rules 'aws-*,stripe-*'`. Only
+    /// `*` is supported as a wildcard. By default, every rule is used.
+    #[arg(long, value_name = "GLOBS", value_delimiter = ',')]
+    rules: Vec<String>,
+
+    /// Do not scan for rules whose IDs match one of these globs.
+    ///
+    /// Takes a comma-separated list, e.g. `--exclude-rules 'generic-*'`.
+    /// Applied after `--rules`.
+    #[arg(long, value_name = "GLOBS", value_delimiter = ',')]
+    exclude_rules: Vec<String>,
+
+    /// Also report high-entropy values assigned to secret-like names, even
+    /// when no rule recognizes the value.
+    ///
+    /// Takes an optional comma-separated list of name keywords, e.g.
+    /// `--secret-names=apiKey,clientSecret`. Without a list, `apiKey`,
+    /// `secret`, `token`, and `password` are used.
+    #[arg(long, value_name = "KEYWORDS", value_delimiter = ',')]
+    #[arg(num_args = 0.., require_equals = true)]
+    secret_names: Option<Vec<String>>,
+
+    /// Also report credentials sent in authentication headers, e.g.
+    /// `Authorization: Bearer ...`, even when no rule recognizes them.
+    ///
+    /// Takes an optional comma-separated list of header names, e.g.
+    /// `--auth-headers=X-Api-Key,X-Session`. Without a list, common headers
+    /// like `Authorization` and `X-API-Key` are used.
+    #[arg(long, value_name = "HEADERS", value_delimiter = ',')]
+    #[arg(num_args = 0.., require_equals = true)]
+    auth_headers: Option<Vec<String>>,
+
+    /// Path to a file listing scripts and rules to ignore.
+    ///
+    /// Each line is a glob matching script URL paths, e.g. `/vendor/*`, or
+    /// rule IDs prefixed with `rule:`, e.g. `rule:generic-*`. Lines starting
+    /// with `#` are comments. By default, `.keyhunterignore` in the current
+    /// directory is used if it exists.
+    #[arg(long, value_name = "FILE")]
+    #[arg(value_hint = ValueHint::FilePath)]
+    ignore_file: Option<PathBuf>,
+
+    /// Only report keys that are not in a previous report.
+    ///
+    /// Takes a report written with `--format json`. Keys are matched by rule,
+    /// secret, and script URL. Keys from the previous report that were not
+    /// found again are listed as resolved.
+    #[arg(long, value_name = "REPORT")]
+    #[arg(value_hint = ValueHint::FilePath)]
+    since: Option<PathBuf>,
+
+    /// Also print URLs and API paths referenced by scripts, e.g.
+    /// `fetch("/api/v1/users")`.
+    #[arg(long)]
+    endpoints: bool,
+
+    /// Record each site in this file once it has been scanned.
+    ///
+    /// Use with `--resume` to continue an interrupted multi-site scan.
+    #[arg(long, value_name = "FILE")]
+    #[arg(value_hint = ValueHint::FilePath)]
+    checkpoint: Option<PathBuf>,
+
+    /// Skip sites that a previous run recorded in the `--checkpoint` file.
+    #[arg(long, requires = "checkpoint")]
+    resume: bool,
+
+    /// Report keys sorted by script URL and position once scanning finishes,
+    /// instead of as they are found. Makes output reproducible across runs.
+    #[arg(long)]
+    sort: bool,
+
+    /// Print the number of keys found on each script host and by each rule
+    /// after scanning.
+    #[arg(long)]
+    summary: bool,
+
+    /// Give up checking a script for API keys after this many seconds.
+    ///
+    /// Protects large scans from getting stuck on huge minified bundles.
+    /// Abandoned scripts are reported as errors. By default, there is no
+    /// limit.
+    #[arg(long, value_name = "SECONDS")]
+    extract_timeout: Option<u64>,
+
+    /// Crawl each site once per locale, sent as the `Accept-Language` header.
+    ///
+    /// Sites that localize their pages may serve different scripts to
+    /// different languages. May be repeated, e.g. `--locale en-US --locale
+    /// de-DE`. By default, sites are crawled once in `en-US`.
+    #[arg(long, value_name = "LANGUAGE")]
+    locale: Vec<String>,
+
+    /// Stop crawling a site once this many keys have been found on it.
+    ///
+    /// Useful when a site is obviously leaking keys and checking every page
+    /// is not worth the wait. By default, sites are crawled completely.
+    #[arg(long, value_name = "N")]
+    max_keys: Option<usize>,
+
+    /// Stop scanning once this many scripts have been checked, counted
+    /// across every site. Remaining sites are skipped.
+    ///
+    /// A single page can reference hundreds of third-party scripts, which can
+    /// make a scan take much longer than `--max-walks` suggests. By default,
+    /// every script is checked.
+    #[arg(long, value_name = "N")]
+    max_scripts: Option<usize>,
+
+    /// Maximum number of sites to scan at once.
+    ///
+    /// Only useful when scanning multiple sites. Must be greater than 0.
+    #[arg(long, short = 'j')]
+    #[arg(default_value = "4")]
+    concurrency: usize,
 }
 
 #[derive(Debug, Default, Clone, Copy, Serialize, Deserialize, clap::ValueEnum)]
@@ -112,14 +275,27 @@ impl<S: AsRef<str>> From<S> for OutputFormat {
 impl Cli {
     const DEFAULT_MAX_WALKS: usize = 20;
 
-    pub fn entrypoint(&self) -> &String {
-        &self.entrypoint
+    pub fn entrypoints(&self) -> &[String] {
+        self.entrypoints.as_slice()
+    }
+
+    pub fn concurrency(&self) -> usize {
+        if self.concurrency == 0 {
+            panic!("concurrency arg must be greater than 0.");
+        }
+        self.concurrency
     }
 
     pub fn log_level_filter(&self) -> log::LevelFilter {
         self.verbose.log_level_filter()
     }
 
+    /// Show extra details about each finding, such as the rule's pattern.
+    /// Enabled by `-v` and above.
+    pub fn is_verbose(&self) -> bool {
+        self.log_level_filter() > log::LevelFilter::Error
+    }
+
     pub fn is_redacted(&self) -> bool {
         self.redact
     }
@@ -142,9 +318,100 @@ impl Cli {
     pub fn headers(&self) -> &[(String, String)] {
         self.header.as_slice()
     }
+
+    pub fn insecure(&self) -> bool {
+        self.insecure
+    }
     pub fn format(&self) -> OutputFormat {
         self.format
     }
+
+    pub fn list_scripts(&self) -> bool {
+        self.list_scripts
+    }
+
+    pub fn scan_json(&self) -> bool {
+        self.scan_json
+    }
+
+    /// Config file paths to check on each site. Empty unless probing is
+    /// enabled.
+    pub fn probe_paths(&self) -> Vec<String> {
+        let defaults = if self.probe_config || !self.probe_path.is_empty() {
+            &ApiKeyCollector::DEFAULT_CONFIG_PATHS[..]
+        } else {
+            &[]
+        };
+        defaults
+            .iter()
+            .map(ToString::to_string)
+            .chain(self.probe_path.iter().cloned())
+            .collect()
+    }
+
+    pub fn since(&self) -> Option<&PathBuf> {
+        self.since.as_ref()
+    }
+
+    pub fn endpoints(&self) -> bool {
+        self.endpoints
+    }
+
+    pub fn checkpoint(&self) -> Option<&PathBuf> {
+        self.checkpoint.as_ref()
+    }
+
+    pub fn resume(&self) -> bool {
+        self.resume
+    }
+
+    pub fn sort(&self) -> bool {
+        self.sort
+    }
+
+    pub fn summary(&self) -> bool {
+        self.summary
+    }
+
+    pub fn extract_timeout(&self) -> Option<Duration> {
+        self.extract_timeout.map(Duration::from_secs)
+    }
+
+    pub fn locales(&self) -> &[String] {
+        &self.locale
+    }
+
+    pub fn max_keys(&self) -> Option<usize> {
+        self.max_keys
+    }
+
+    pub fn max_scripts(&self) -> Option<usize> {
+        self.max_scripts
+    }
+
+    pub fn config(&self) -> Option<&PathBuf> {
+        self.config.as_ref()
+    }
+
+    pub fn ignore_file(&self) -> Option<&PathBuf> {
+        self.ignore_file.as_ref()
+    }
+
+    pub fn rules(&self) -> &[String] {
+        &self.rules
+    }
+
+    pub fn exclude_rules(&self) -> &[String] {
+        &self.exclude_rules
+    }
+
+    pub fn secret_names(&self) -> Option<&[String]> {
+        self.secret_names.as_deref()
+    }
+
+    pub fn auth_headers(&self) -> Option<&[String]> {
+        self.auth_headers.as_deref()
+    }
 }
 
 /// Parse a single key-value pair
@@ -1,2 +1,4 @@
+pub mod checkpoint;
 pub mod cli;
 pub mod runner;
+pub mod signal;
@@ -0,0 +1,52 @@
+/// Copyright © 2024 Don Isaac
+///
+/// This file is part of KeyHunter.
+///
+/// KeyHunter is free software: you can redistribute it and/or modify it
+/// under the terms of the GNU General Public License as published by the Free
+/// Software Foundation, either version 3 of the License, or (at your option)
+/// any later version.
+///
+/// KeyHunter is distributed in the hope that it will be useful, but WITHOUT
+/// ANY WARRANTY; without even the implied warranty of MERCHANTABILITY or
+/// FITNESS FOR A PARTICULAR PURPOSE. See the GNU General Public License for
+/// more details.
+///
+/// You should have received a copy of the GNU General Public License along with
+/// KeyHunter. If not, see <https://www.gnu.org/licenses/>.
+use std::{
+    process,
+    sync::atomic::{AtomicBool, Ordering},
+};
+
+use keyhunter::CancellationToken;
+
+/// Exit code used when the scan was stopped with Ctrl-C (128 + `SIGINT`)
+pub const EXIT_INTERRUPTED: u8 = 130;
+
+/// Set once the user presses Ctrl-C.
+static INTERRUPTED: AtomicBool = AtomicBool::new(false);
+
+/// Cancel `cancel` when the user presses Ctrl-C, so that the scan can stop
+/// early and still report what it found. Pressing Ctrl-C again kills the
+/// process immediately.
+///
+/// If the handler cannot be installed, Ctrl-C kills the process as usual.
+pub fn cancel_on_interrupt(cancel: CancellationToken) {
+    // the handler runs on a dedicated thread, not in signal context
+    let installed = ctrlc::set_handler(move || {
+        if INTERRUPTED.swap(true, Ordering::Relaxed) {
+            process::exit(EXIT_INTERRUPTED.into());
+        }
+        warn!("Interrupted, stopping scan. Press Ctrl-C again to exit immediately.");
+        cancel.cancel();
+    });
+    if let Err(error) = installed {
+        warn!("Could not handle Ctrl-C: {error}");
+    }
+}
+
+/// Returns `true` if the user pressed Ctrl-C.
+pub fn interrupted() -> bool {
+    INTERRUPTED.load(Ordering::Relaxed)
+}
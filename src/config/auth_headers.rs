@@ -0,0 +1,164 @@
+/// Copyright © 2024 Don Isaac
+///
+/// This file is part of KeyHunter.
+///
+/// KeyHunter is free software: you can redistribute it and/or modify it
+/// under the terms of the GNU General Public License as published by the Free
+/// Software Foundation, either version 3 of the License, or (at your option)
+/// any later version.
+///
+/// KeyHunter is distributed in the hope that it will be useful, but WITHOUT
+/// ANY WARRANTY; without even the implied warranty of MERCHANTABILITY or
+/// FITNESS FOR A PARTICULAR PURPOSE. See the GNU General Public License for
+/// more details.
+///
+/// You should have received a copy of the GNU General Public License along with
+/// KeyHunter. If not, see <https://www.gnu.org/licenses/>.
+use regex::Regex;
+
+use super::{CustomRule, Pattern};
+
+/// Report credentials sent in well-known authentication headers, e.g.
+/// `fetch(url, { headers: { Authorization: "Bearer ..." } })`, even when no
+/// rule recognizes the credential itself.
+///
+/// Header names are matched against variable and property names exactly,
+/// ignoring case. For values with an auth scheme, e.g. `Bearer` or `Basic`,
+/// only the credential after the scheme is reported. Credentials must be at
+/// least [`AuthHeaders::with_min_length`] characters long and have a Shannon
+/// entropy of at least [`AuthHeaders::with_entropy`].
+///
+/// Found values are reported by a rule with the ID [`AuthHeaders::RULE_ID`].
+/// See [`Config::with_auth_headers`].
+///
+/// ## Example
+/// ```
+/// use keyhunter::{AuthHeaders, Config};
+///
+/// let config = Config::empty().with_auth_headers(AuthHeaders::default());
+/// assert!(config.find_rule_id(AuthHeaders::RULE_ID).is_some());
+/// ```
+///
+/// [`Config::with_auth_headers`]: crate::Config::with_auth_headers
+#[derive(Debug, Clone, PartialEq)]
+#[must_use]
+pub struct AuthHeaders {
+    headers: Vec<String>,
+    entropy: f32,
+    min_length: usize,
+}
+
+impl Default for AuthHeaders {
+    fn default() -> Self {
+        Self::new(Self::DEFAULT_HEADERS.iter().copied())
+    }
+}
+
+impl AuthHeaders {
+    /// ID of the rule that reports credentials found in headers.
+    pub const RULE_ID: &'static str = "auth-header";
+    pub const DEFAULT_HEADERS: [&'static str; 9] = [
+        "Authorization",
+        "Proxy-Authorization",
+        "X-API-Key",
+        "Api-Key",
+        "X-Auth-Token",
+        "X-Access-Token",
+        "X-API-Token",
+        "X-Goog-Api-Key",
+        "Private-Token",
+    ];
+    pub const DEFAULT_ENTROPY: f32 = 3.0;
+    pub const DEFAULT_MIN_LENGTH: usize = 8;
+
+    /// Report credentials sent in any of `headers`.
+    pub fn new<I, S>(headers: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        Self {
+            headers: headers.into_iter().map(Into::into).collect(),
+            entropy: Self::DEFAULT_ENTROPY,
+            min_length: Self::DEFAULT_MIN_LENGTH,
+        }
+    }
+
+    pub fn headers(&self) -> &[String] {
+        &self.headers
+    }
+
+    /// Only report credentials with a Shannon entropy of at least `entropy`.
+    /// Default [`AuthHeaders::DEFAULT_ENTROPY`].
+    pub fn with_entropy(mut self, entropy: f32) -> Self {
+        self.entropy = entropy;
+        self
+    }
+
+    /// Only report credentials at least `min_length` characters long.
+    /// Default [`AuthHeaders::DEFAULT_MIN_LENGTH`].
+    pub fn with_min_length(mut self, min_length: usize) -> Self {
+        self.min_length = min_length.max(1);
+        self
+    }
+
+    /// Build the rule that reports credentials in headers. [`None`] when
+    /// there are no headers.
+    pub(super) fn to_rule(&self) -> Option<CustomRule> {
+        if self.headers.is_empty() {
+            return None;
+        }
+        let names: Vec<String> = self
+            .headers
+            .iter()
+            .map(|header| regex::escape(header))
+            .collect();
+        let name = Regex::new(&format!("(?i)^(?:{})$", names.join("|"))).ok()?;
+        // the last word, so `Bearer <token>` only reports the token
+        let value = Regex::new(&format!(r"\S{{{},}}$", self.min_length)).ok()?;
+
+        Some(
+            CustomRule::new(Self::RULE_ID, Pattern::from(value))
+                .with_description("Found a credential sent in an authentication header")
+                .with_name(Pattern::from(name))
+                .with_entropy(self.entropy),
+        )
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_to_rule() {
+        let rule = AuthHeaders::default().to_rule().unwrap();
+        let name = rule.name.unwrap();
+        for matching in ["Authorization", "authorization", "x-api-key", "X-API-KEY"] {
+            assert!(name.matches(matching), "{matching}");
+        }
+        for other in ["authorizationUrl", "apiKey", "x-api-key-id", "headers"] {
+            assert!(!name.matches(other), "{other}");
+        }
+
+        assert_eq!(
+            rule.value.captures("Bearer sk-abc123def456"),
+            vec![(7, "sk-abc123def456")]
+        );
+        assert_eq!(
+            rule.value.captures("abc123def456"),
+            vec![(0, "abc123def456")]
+        );
+        assert!(rule.value.captures("Bearer abc").is_empty());
+
+        let rule = AuthHeaders::default()
+            .with_min_length(3)
+            .with_entropy(1.0)
+            .to_rule()
+            .unwrap();
+        assert_eq!(rule.value.captures("Bearer abc"), vec![(7, "abc")]);
+        assert_eq!(rule.entropy, Some(1.0));
+
+        assert!(AuthHeaders::new(Vec::<String>::new()).to_rule().is_none());
+    }
+}
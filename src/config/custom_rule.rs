@@ -0,0 +1,175 @@
+/// Copyright © 2024 Don Isaac
+///
+/// This file is part of KeyHunter.
+///
+/// KeyHunter is free software: you can redistribute it and/or modify it
+/// under the terms of the GNU General Public License as published by the Free
+/// Software Foundation, either version 3 of the License, or (at your option)
+/// any later version.
+///
+/// KeyHunter is distributed in the hope that it will be useful, but WITHOUT
+/// ANY WARRANTY; without even the implied warranty of MERCHANTABILITY or
+/// FITNESS FOR A PARTICULAR PURPOSE. See the GNU General Public License for
+/// more details.
+///
+/// You should have received a copy of the GNU General Public License along with
+/// KeyHunter. If not, see <https://www.gnu.org/licenses/>.
+use tinyvec::TinyVec;
+
+use super::{Pattern, Validator};
+
+/// A user-defined rule that can be added to a [`Config`].
+///
+/// ## Example
+/// ```
+/// use keyhunter::{Config, CustomRule, Pattern};
+///
+/// let rule = CustomRule::new("acme-api-key", Pattern::glob("acme_*"))
+///     .with_description("Found an ACME API key")
+///     .with_name(Pattern::glob("*acme*"));
+///
+/// let config = Config::empty().with_custom_rule(rule);
+/// assert_eq!(config.len(), 1);
+/// ```
+///
+/// [`Config`]: crate::Config
+#[derive(Debug)]
+#[must_use]
+pub struct CustomRule {
+    pub(super) id: String,
+    pub(super) description: String,
+    pub(super) name: Option<Pattern>,
+    pub(super) value: Pattern,
+    pub(super) keywords: TinyVec<[String; 1]>,
+    pub(super) tags: Vec<String>,
+    pub(super) entropy: Option<f32>,
+    pub(super) validator: Option<Validator>,
+    pub(super) case_insensitive: bool,
+    pub(super) path: Option<Pattern>,
+    pub(super) remediation: Option<String>,
+    pub(super) secret_group: Option<usize>,
+}
+
+impl CustomRule {
+    /// Create a new rule that reports values matching `value`.
+    ///
+    /// `id` is displayed to users when the rule finds a secret.
+    pub fn new<S, P>(id: S, value: P) -> Self
+    where
+        S: Into<String>,
+        P: Into<Pattern>,
+    {
+        Self {
+            id: id.into(),
+            description: String::new(),
+            name: None,
+            value: value.into(),
+            keywords: TinyVec::default(),
+            tags: Vec::new(),
+            entropy: None,
+            validator: None,
+            case_insensitive: false,
+            path: None,
+            remediation: None,
+            secret_group: None,
+        }
+    }
+
+    /// Set the description reported alongside found secrets.
+    pub fn with_description<S: Into<String>>(mut self, description: S) -> Self {
+        self.description = description.into();
+        self
+    }
+
+    /// Only report values assigned to variables or properties whose name
+    /// matches this pattern.
+    ///
+    /// By default, values are checked regardless of where they are assigned.
+    pub fn with_name<P: Into<Pattern>>(mut self, name: P) -> Self {
+        self.name = Some(name.into());
+        self
+    }
+
+    /// Only apply this rule to scripts whose URL path matches this pattern,
+    /// e.g. `\.env$`.
+    ///
+    /// By default, rules apply to all scripts.
+    pub fn with_path<P: Into<Pattern>>(mut self, path: P) -> Self {
+        self.path = Some(path.into());
+        self
+    }
+
+    /// Tell users what to do when this rule finds a secret, e.g. "Rotate the
+    /// key in the ACME dashboard". Shown alongside reported keys.
+    pub fn with_remediation<S: Into<String>>(mut self, remediation: S) -> Self {
+        self.remediation = Some(remediation.into());
+        self
+    }
+
+    /// Only report the text captured by regex group `group` as the secret,
+    /// e.g. `2` for `(\w+)_key=(\w+)`. Group `0` is the entire match.
+    ///
+    /// By default, the entire match and every capture group are checked,
+    /// which can report the name half of a multi-group pattern as a secret.
+    /// Ignored by string and glob patterns.
+    ///
+    /// ## Example
+    /// ```
+    /// use keyhunter::{Config, CustomRule};
+    /// use regex::Regex;
+    ///
+    /// let rule = CustomRule::new("acme-key", Regex::new(r"(acme)_key=(\w+)").unwrap())
+    ///     .with_secret_group(2);
+    /// let config = Config::empty().with_custom_rule(rule);
+    /// let found: Vec<_> = config.check_values("acme_key=abc123").collect();
+    /// assert_eq!(found.len(), 1);
+    /// assert_eq!(found[0].2, "abc123");
+    /// ```
+    pub fn with_secret_group(mut self, group: usize) -> Self {
+        self.secret_group = Some(group);
+        self
+    }
+
+    /// Add keywords associated with this rule.
+    pub fn with_keywords<I, S>(mut self, keywords: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        self.keywords.extend(keywords.into_iter().map(Into::into));
+        self
+    }
+
+    /// Add tags (categories) this rule belongs to, e.g. `cloud` or `payment`.
+    pub fn with_tags<I, S>(mut self, tags: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        self.tags.extend(tags.into_iter().map(Into::into));
+        self
+    }
+
+    /// Only report values with a Shannon entropy of at least `entropy`.
+    pub fn with_entropy(mut self, entropy: f32) -> Self {
+        self.entropy = Some(entropy);
+        self
+    }
+
+    /// Ignore case when matching names and values, e.g. so `acme_*` also
+    /// matches `ACME_1234`.
+    ///
+    /// See [`Pattern::into_case_insensitive`]. Default `false`
+    pub fn with_case_insensitive(mut self, yes: bool) -> Self {
+        self.case_insensitive = yes;
+        self
+    }
+
+    /// Only report secrets for which `validator` returns `true`.
+    ///
+    /// See [`Validator`].
+    pub fn with_validator<V: Into<Validator>>(mut self, validator: V) -> Self {
+        self.validator = Some(validator.into());
+        self
+    }
+}
@@ -14,16 +14,40 @@
 ///
 /// You should have received a copy of the GNU General Public License along with
 /// KeyHunter. If not, see <https://www.gnu.org/licenses/>.
+use std::path::{Path, PathBuf};
+
+use miette::{miette, Context as _, IntoDiagnostic as _, Result};
 use serde::Deserialize;
 use tinyvec::TinyVec;
 
 #[derive(Debug, Deserialize)]
 pub struct GitLeaksConfig {
     pub title: Option<String>,
+    /// Other configs whose rules this config builds on. Resolved by
+    /// [`GitLeaksConfig::load`].
+    pub extend: Option<GitLeaksExtend>,
     pub allowlist: Option<GitLeaksAllowList>,
+    /// Configs that only extend another config may not have rules of their
+    /// own
+    #[serde(default)]
     pub rules: Vec<GitLeaksRule>,
 }
 
+/// The `[extend]` table of a gitleaks config.
+///
+/// see: [Configuration](https://github.com/gitleaks/gitleaks#configuration)
+#[derive(Debug, Default, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct GitLeaksExtend {
+    /// Extend gitleaks' default config
+    pub use_default: Option<bool>,
+    /// Extend the config at this path. Relative paths are resolved against
+    /// the directory of the extending config.
+    pub path: Option<PathBuf>,
+    /// IDs of extended rules to leave out
+    pub disabled_rules: Option<Vec<String>>,
+}
+
 #[derive(Debug, Deserialize)]
 pub struct GitLeaksAllowList {
     pub description: Option<String>,
@@ -37,14 +61,101 @@ pub struct GitLeaksRule {
     pub regex: String,
     pub keywords: Option<TinyVec<[String; 1]>>,
     pub entropy: Option<f32>,
+    pub tags: Option<Vec<String>>,
+    /// Only apply the rule to files whose path matches this regex
+    pub path: Option<String>,
+    /// The regex capture group holding the secret, e.g. `2` when group `1`
+    /// captures a URL's scheme
+    #[serde(rename = "secretGroup")]
+    pub secret_group: Option<usize>,
+    /// KeyHunter extension that overrides whether the rule's regex ignores
+    /// case. When unset, rules are case-insensitive if their regex starts
+    /// with `(?i)`.
+    #[serde(rename = "caseInsensitive")]
+    pub case_insensitive: Option<bool>,
+    /// KeyHunter extension describing what to do when the rule finds a
+    /// secret. Defaults to advice for the rule's provider.
+    pub remediation: Option<String>,
 }
 
 impl GitLeaksConfig {
     pub const DEFAULT_CONFIG: &'static str = include_str!("./gitleaks.toml");
 
+    /// How many configs deep `[extend]` may be nested. Also stops configs
+    /// that extend each other from being loaded forever.
+    const MAX_EXTEND_DEPTH: usize = 8;
+
     pub fn default_config() -> Self {
         toml::from_str(Self::DEFAULT_CONFIG).unwrap()
     }
+
+    /// Parse a config, then load and merge in any configs it extends.
+    ///
+    /// Relative `[extend]` paths are resolved against `dir`, or the current
+    /// directory when there is none. Rules in the extending config replace
+    /// extended rules with the same ID.
+    pub fn load(source_text: &str, dir: Option<&Path>) -> Result<Self> {
+        Self::load_nested(source_text, dir, 0)
+    }
+
+    fn load_nested(source_text: &str, dir: Option<&Path>, depth: usize) -> Result<Self> {
+        let mut config: Self = toml::from_str(source_text).into_diagnostic()?;
+        let Some(extend) = config.extend.take() else {
+            return Ok(config);
+        };
+        if depth >= Self::MAX_EXTEND_DEPTH {
+            return Err(miette!(
+                "Configs may only be extended {} levels deep. Do two configs extend each other?",
+                Self::MAX_EXTEND_DEPTH
+            ));
+        }
+
+        let mut extended = vec![];
+        if extend.use_default == Some(true) {
+            extended.push(Self::default_config());
+        }
+        if let Some(path) = extend.path {
+            let path = match dir {
+                Some(dir) => dir.join(path),
+                None => path,
+            };
+            let source_text = std::fs::read_to_string(&path)
+                .into_diagnostic()
+                .with_context(|| format!("Failed to read extended config {}", path.display()))?;
+            let base = Self::load_nested(&source_text, path.parent(), depth + 1)
+                .with_context(|| format!("Failed to parse extended config {}", path.display()))?;
+            extended.push(base);
+        }
+
+        let disabled = extend.disabled_rules.unwrap_or_default();
+        let mut rules: Vec<GitLeaksRule> = vec![];
+        for rule in extended.into_iter().flat_map(|base| base.rules) {
+            let overridden = disabled.contains(&rule.id)
+                || config.rules.iter().any(|own| own.id == rule.id)
+                || rules.iter().any(|seen| seen.id == rule.id);
+            if !overridden {
+                rules.push(rule);
+            }
+        }
+        rules.append(&mut config.rules);
+        config.rules = rules;
+
+        Ok(config)
+    }
+}
+
+impl GitLeaksRule {
+    /// Separator between a keyword and its value in gitleaks' keyword-context
+    /// regexes, e.g. the `=` in `api_key = "..."`.
+    const ASSIGNMENT_OPERATOR: &'static str = ":{1,3}=";
+
+    /// Returns `true` if this rule matches secrets by a distinctive prefix or
+    /// format (e.g. `ghp_...`) instead of by a keyword appearing near the
+    /// secret. Keyword-context rules also match ordinary identifiers and
+    /// hashes, so they produce far more false positives.
+    pub fn is_high_confidence(&self) -> bool {
+        self.id != "generic-api-key" && !self.regex.contains(Self::ASSIGNMENT_OPERATOR)
+    }
 }
 
 #[cfg(test)]
@@ -58,6 +169,48 @@ mod test {
         assert!(config.rules.len() > 100);
     }
 
+    #[test]
+    fn test_extend_default() {
+        let config = GitLeaksConfig::load(
+            r#"
+[extend]
+useDefault = true
+disabledRules = ["generic-api-key"]
+
+[[rules]]
+id = "aws-access-token"
+description = "Overridden AWS rule"
+regex = 'AKIA[0-9A-Z]{16}'
+"#,
+            None,
+        )
+        .unwrap();
+        let default = GitLeaksConfig::default_config();
+        assert!(config.extend.is_none());
+        assert_eq!(config.rules.len(), default.rules.len() - 1);
+        assert!(config.rules.iter().all(|rule| rule.id != "generic-api-key"));
+
+        let aws: Vec<_> = config
+            .rules
+            .iter()
+            .filter(|rule| rule.id == "aws-access-token")
+            .collect();
+        assert_eq!(aws.len(), 1);
+        assert_eq!(aws[0].description, "Overridden AWS rule");
+    }
+
+    #[test]
+    fn test_is_high_confidence() {
+        let config = GitLeaksConfig::default_config();
+        let rule = |id: &str| config.rules.iter().find(|rule| rule.id == id).unwrap();
+
+        assert!(rule("github-pat").is_high_confidence());
+        assert!(rule("aws-access-token").is_high_confidence());
+        assert!(!rule("generic-api-key").is_high_confidence());
+        // matched by a keyword, not by the token's format
+        assert!(!rule("heroku-api-key").is_high_confidence());
+    }
+
     #[test]
     fn test_parse_simple() {
         let text = r#"
@@ -85,6 +238,22 @@ mod test {
         assert_eq!(config.rules.len(), 2);
     }
 
+    #[test]
+    fn test_parse_tags() {
+        let text = r#"
+            [[rules]]
+            id = "foo"
+            description = "Found a foo api key"
+            regex = "foo_[a-z]{10}"
+            tags = ["cloud", "payment"]
+        "#;
+        let config: GitLeaksConfig = toml::from_str(text).unwrap();
+        assert_eq!(
+            config.rules[0].tags.as_deref(),
+            Some(["cloud".to_string(), "payment".to_string()].as_slice())
+        );
+    }
+
     #[test]
     fn test_parse() {
         let config: GitLeaksConfig = toml::from_str(GitLeaksConfig::DEFAULT_CONFIG).unwrap();
@@ -0,0 +1,169 @@
+/// Copyright © 2024 Don Isaac
+///
+/// This file is part of KeyHunter.
+///
+/// KeyHunter is free software: you can redistribute it and/or modify it
+/// under the terms of the GNU General Public License as published by the Free
+/// Software Foundation, either version 3 of the License, or (at your option)
+/// any later version.
+///
+/// KeyHunter is distributed in the hope that it will be useful, but WITHOUT
+/// ANY WARRANTY; without even the implied warranty of MERCHANTABILITY or
+/// FITNESS FOR A PARTICULAR PURPOSE. See the GNU General Public License for
+/// more details.
+///
+/// You should have received a copy of the GNU General Public License along with
+/// KeyHunter. If not, see <https://www.gnu.org/licenses/>.
+use std::{fs, io, path::Path};
+
+use miette::{Context as _, IntoDiagnostic as _, Result};
+use url::Url;
+
+use super::Pattern;
+
+/// Scripts and rules to leave out of scans, usually loaded from a
+/// `.keyhunterignore` file.
+///
+/// Like a `.gitignore`, each line is a glob and lines starting with `#` are
+/// comments.
+/// - Lines starting with `rule:` match rule IDs, e.g. `rule:generic-*`.
+/// - Lines containing `://` match entire script URLs, e.g.
+///   `https://cdn.example.com/*`.
+/// - Other lines match script URL paths, e.g. `/static/vendor/*`. Paths
+///   without a leading `/` match at any depth, so `vendor/*` also matches
+///   `/assets/vendor/lib.js`.
+///
+/// ## Example
+/// ```
+/// use keyhunter::IgnoreFile;
+/// use url::Url;
+///
+/// let ignore = IgnoreFile::parse(
+///     "# accepted findings
+/// /vendor/*
+/// rule:generic-*",
+/// );
+/// assert!(ignore.ignores_url(&Url::parse("https://example.com/vendor/lib.js").unwrap()));
+/// assert!(ignore.ignores_rule("generic-api-key"));
+/// assert!(!ignore.ignores_rule("aws-access-token"));
+/// ```
+#[derive(Debug, Default, Clone)]
+pub struct IgnoreFile {
+    /// Matched against script URL paths
+    paths: Vec<Pattern>,
+    /// Matched against entire script URLs
+    urls: Vec<Pattern>,
+    rules: Vec<Pattern>,
+}
+
+impl IgnoreFile {
+    /// Name of the ignore file keyhunter looks for in the current directory.
+    pub const FILE_NAME: &'static str = ".keyhunterignore";
+
+    /// Parse the contents of an ignore file.
+    pub fn parse(source_text: &str) -> Self {
+        let mut ignore = Self::default();
+        for line in source_text.lines().map(str::trim) {
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            if let Some(rule) = line.strip_prefix("rule:") {
+                ignore.rules.push(Pattern::glob(rule.trim()));
+            } else if line.contains("://") {
+                ignore.urls.push(Pattern::glob(line));
+            } else if line.starts_with('/') || line.starts_with('*') {
+                ignore.paths.push(Pattern::glob(line));
+            } else {
+                ignore.paths.push(Pattern::glob(format!("*/{line}")));
+            }
+        }
+        ignore
+    }
+
+    /// Load an ignore file.
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<Self> {
+        let path = path.as_ref();
+        fs::read_to_string(path)
+            .map(|source_text| Self::parse(&source_text))
+            .into_diagnostic()
+            .with_context(|| format!("Failed to read ignore file at {}", path.display()))
+    }
+
+    /// Load `.keyhunterignore` from `dir`, if it has one.
+    pub fn find<P: AsRef<Path>>(dir: P) -> Result<Option<Self>> {
+        let path = dir.as_ref().join(Self::FILE_NAME);
+        match fs::read_to_string(&path) {
+            Ok(source_text) => Ok(Some(Self::parse(&source_text))),
+            Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(None),
+            Err(e) => Err(e)
+                .into_diagnostic()
+                .with_context(|| format!("Failed to read ignore file at {}", path.display())),
+        }
+    }
+
+    /// Returns `true` if the file has no patterns.
+    pub fn is_empty(&self) -> bool {
+        self.paths.is_empty() && self.urls.is_empty() && self.rules.is_empty()
+    }
+
+    /// Returns `true` if scripts at `url` should not be checked.
+    pub fn ignores_url(&self, url: &Url) -> bool {
+        self.paths.iter().any(|path| path.matches(url.path()))
+            || self
+                .urls
+                .iter()
+                .any(|pattern| pattern.matches(url.as_str()))
+    }
+
+    /// Returns `true` if keys found by the rule with this display ID should
+    /// not be reported.
+    pub fn ignores_rule(&self, rule_id: &str) -> bool {
+        self.rules.iter().any(|rule| rule.matches(rule_id))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_parse() {
+        let ignore = IgnoreFile::parse(
+            "
+# comments and blank lines are skipped
+
+/static/vendor/*
+third_party/*
+https://cdn.example.com/*
+rule: stripe-*
+",
+        );
+        let ignored = |url: &str| ignore.ignores_url(&Url::parse(url).unwrap());
+
+        assert!(ignored("https://example.com/static/vendor/lib.js"));
+        assert!(!ignored("https://example.com/static/app.js"));
+        assert!(!ignored("https://example.com/other/static/vendor/lib.js"));
+        // paths without a leading slash match at any depth
+        assert!(ignored("https://example.com/third_party/lib.js"));
+        assert!(ignored("https://example.com/assets/third_party/lib.js"));
+        assert!(ignored("https://cdn.example.com/app.js"));
+        assert!(!ignored("https://example.com/cdn.example.com/app.js"));
+
+        assert!(ignore.ignores_rule("stripe-access-token"));
+        assert!(!ignore.ignores_rule("aws-access-token"));
+
+        assert!(IgnoreFile::parse("# nothing\n\n").is_empty());
+    }
+
+    #[test]
+    fn test_find() {
+        let dir = std::env::temp_dir().join(format!("keyhunter-ignore-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        assert!(IgnoreFile::find(&dir).unwrap().is_none());
+
+        fs::write(dir.join(IgnoreFile::FILE_NAME), "rule:generic-*\n").unwrap();
+        let ignore = IgnoreFile::find(&dir).unwrap().unwrap();
+        fs::remove_dir_all(&dir).unwrap();
+        assert!(ignore.ignores_rule("generic-api-key"));
+    }
+}
@@ -0,0 +1,183 @@
+/// Copyright © 2024 Don Isaac
+///
+/// This file is part of KeyHunter.
+///
+/// KeyHunter is free software: you can redistribute it and/or modify it
+/// under the terms of the GNU General Public License as published by the Free
+/// Software Foundation, either version 3 of the License, or (at your option)
+/// any later version.
+///
+/// KeyHunter is distributed in the hope that it will be useful, but WITHOUT
+/// ANY WARRANTY; without even the implied warranty of MERCHANTABILITY or
+/// FITNESS FOR A PARTICULAR PURPOSE. See the GNU General Public License for
+/// more details.
+///
+/// You should have received a copy of the GNU General Public License along with
+/// KeyHunter. If not, see <https://www.gnu.org/licenses/>.
+use miette::Diagnostic;
+use regex::{Regex, RegexBuilder};
+use thiserror::Error;
+
+use super::Pattern;
+
+/// Caps on how large compiled regexes may grow.
+///
+/// Rules loaded from untrusted configs may contain patterns that compile
+/// into enormous automata (e.g. `(?:\w{100}){100}`), using huge amounts of
+/// memory and slowing down every scan. Patterns that exceed these limits are
+/// rejected instead of compiled. See [`Config::with_regex_limits`] and
+/// [`Config::rejected_rules`].
+///
+/// ## Example
+/// ```
+/// use keyhunter::{Config, CustomRule, RegexLimits};
+/// use regex::Regex;
+///
+/// let huge = Regex::new(r"(?:[a-z0-9]{50}){50}").unwrap();
+/// let config = Config::empty()
+///     .with_regex_limits(RegexLimits::default().with_size_limit(1 << 16))
+///     .with_custom_rule(CustomRule::new("huge", huge));
+/// assert!(config.is_empty());
+/// assert_eq!(config.rejected_rules()[0].id(), "huge");
+/// ```
+///
+/// [`Config::with_regex_limits`]: crate::Config::with_regex_limits
+/// [`Config::rejected_rules`]: crate::Config::rejected_rules
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[must_use]
+pub struct RegexLimits {
+    size_limit: usize,
+    dfa_size_limit: usize,
+}
+
+impl Default for RegexLimits {
+    fn default() -> Self {
+        Self {
+            size_limit: 1 << 20,
+            dfa_size_limit: 2 << 20,
+        }
+    }
+}
+
+impl RegexLimits {
+    /// Maximum size, in bytes, of a compiled regex. Default 1 MiB.
+    ///
+    /// See [`RegexBuilder::size_limit`].
+    pub fn with_size_limit(mut self, bytes: usize) -> Self {
+        self.size_limit = bytes;
+        self
+    }
+
+    /// Maximum size, in bytes, of the cache used by a regex's lazy DFA.
+    /// Default 2 MiB, the same as [`RegexBuilder`]'s.
+    ///
+    /// See [`RegexBuilder::dfa_size_limit`].
+    pub fn with_dfa_size_limit(mut self, bytes: usize) -> Self {
+        self.dfa_size_limit = bytes;
+        self
+    }
+
+    pub fn size_limit(&self) -> usize {
+        self.size_limit
+    }
+
+    pub fn dfa_size_limit(&self) -> usize {
+        self.dfa_size_limit
+    }
+
+    /// Compile `pattern`, failing if it exceeds these limits.
+    pub(super) fn build(
+        &self,
+        pattern: &str,
+        case_insensitive: bool,
+    ) -> Result<Regex, regex::Error> {
+        RegexBuilder::new(pattern)
+            .case_insensitive(case_insensitive)
+            .size_limit(self.size_limit)
+            .dfa_size_limit(self.dfa_size_limit)
+            .build()
+    }
+
+    /// Compile a user-provided pattern within these limits, making it ignore
+    /// case if `case_insensitive` is set. See [`Pattern::into_case_insensitive`].
+    ///
+    /// Regexes are compiled before they reach a [`Pattern`], so they are
+    /// recompiled here. String and glob patterns only become regexes when
+    /// they ignore case.
+    pub(super) fn compile(
+        &self,
+        pattern: Pattern,
+        case_insensitive: bool,
+    ) -> Result<Pattern, regex::Error> {
+        if !case_insensitive && !matches!(pattern, Pattern::Regex(_)) {
+            return Ok(pattern);
+        }
+        self.build(&pattern.to_regex_source(), case_insensitive)
+            .map(Pattern::Regex)
+    }
+}
+
+/// A rule that was skipped while building a [`Config`] because one of its
+/// patterns could not be compiled, e.g. because it exceeded the config's
+/// [`RegexLimits`].
+///
+/// [`Config`]: crate::Config
+#[derive(Debug, Clone, Error, Diagnostic)]
+#[error("Rule {id} was skipped: {reason}")]
+#[diagnostic(help("Simplify the rule's patterns or raise the config's regex size limits"))]
+pub struct RejectedRule {
+    id: String,
+    reason: String,
+}
+
+impl RejectedRule {
+    pub(super) fn new<S: Into<String>>(id: S, error: &regex::Error) -> Self {
+        // regex's messages span multiple lines; keep the first one
+        let reason = error.to_string();
+        let reason = reason.lines().next().unwrap_or_default().to_string();
+        Self {
+            id: id.into(),
+            reason,
+        }
+    }
+
+    /// The display ID of the rejected rule
+    pub fn id(&self) -> &str {
+        &self.id
+    }
+
+    /// Why the rule was rejected
+    pub fn reason(&self) -> &str {
+        &self.reason
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_limits() {
+        let limits = RegexLimits::default();
+        assert_eq!(limits.size_limit(), 1 << 20);
+        // the same cache size regex uses by default
+        assert_eq!(limits.dfa_size_limit(), 2 << 20);
+        assert!(limits.build(r"\bsk_live_[0-9a-zA-Z]{24}\b", false).is_ok());
+        let err = limits.build(r"(?:\w{100}){100}", false).unwrap_err();
+        assert!(matches!(err, regex::Error::CompiledTooBig(_)));
+
+        // globs only become regexes when they ignore case
+        let glob = || Pattern::glob("acme_*");
+        assert!(matches!(
+            limits.compile(glob(), false),
+            Ok(Pattern::Glob(_))
+        ));
+        assert!(matches!(
+            limits.compile(glob(), true),
+            Ok(Pattern::Regex(_))
+        ));
+        let tiny = limits.with_size_limit(16);
+        assert!(tiny.compile(glob(), false).is_ok());
+        assert!(tiny.compile(glob(), true).is_err());
+    }
+}
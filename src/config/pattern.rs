@@ -14,12 +14,20 @@
 ///
 /// You should have received a copy of the GNU General Public License along with
 /// KeyHunter. If not, see <https://www.gnu.org/licenses/>.
-use regex::Regex;
+use regex::{Regex, RegexBuilder};
 
-#[derive(Debug)]
+/// Criteria used to match identifier names and secret values.
+#[derive(Debug, Clone)]
 pub enum Pattern {
+    /// Matches when the regex finds a match anywhere in the input.
     Regex(Regex),
+    /// Matches when the input contains this string as a substring.
     String(String),
+    /// Matches when the entire input matches this glob, e.g. `*secret*`.
+    ///
+    /// `*` matches any sequence of characters (including none) and `?`
+    /// matches exactly one character. All other characters match themselves.
+    Glob(String),
 }
 
 impl Default for Pattern {
@@ -55,19 +63,113 @@ impl std::fmt::Display for Pattern {
                 pat.fmt(f)?;
                 write!(f, "/")
             }
+            Self::Glob(pat) => pat.fmt(f),
         }
     }
 }
 
 impl Pattern {
+    /// Create a [`Pattern::Glob`] pattern.
+    ///
+    /// ## Example
+    /// ```
+    /// use keyhunter::Pattern;
+    ///
+    /// let pattern = Pattern::glob("*secret*");
+    /// assert!(pattern.matches("client_secret_key"));
+    /// assert!(!pattern.matches("client_id"));
+    /// ```
+    pub fn glob<S: Into<String>>(glob: S) -> Self {
+        Self::Glob(glob.into())
+    }
+
+    /// Make this pattern ignore case.
+    ///
+    /// Regexes are recompiled with case-insensitivity enabled. Glob and string
+    /// patterns are converted into equivalent case-insensitive regexes, so a
+    /// string pattern only captures the text it matched instead of the
+    /// surrounding word.
+    ///
+    /// Fails if the case-insensitive regex is too big to compile. Ignoring
+    /// case can make a regex much larger, e.g. when it contains `\w`.
+    ///
+    /// ## Example
+    /// ```
+    /// use keyhunter::Pattern;
+    ///
+    /// let pattern = Pattern::glob("acme_*").into_case_insensitive().unwrap();
+    /// assert!(pattern.matches("ACME_1234"));
+    /// ```
+    pub fn into_case_insensitive(self) -> Result<Self, regex::Error> {
+        RegexBuilder::new(&self.to_regex_source())
+            .case_insensitive(true)
+            .build()
+            .map(Self::Regex)
+    }
+
+    /// The source of an equivalent regex.
+    pub(super) fn to_regex_source(&self) -> String {
+        match self {
+            Self::Regex(regex) => regex.as_str().to_string(),
+            Self::String(s) => regex::escape(s),
+            Self::Glob(glob) => glob_to_regex(glob),
+        }
+    }
+
+    /// Returns `true` if `value` matches this pattern. See [`Pattern`] for how
+    /// each kind of pattern is matched.
     pub fn matches(&self, value: &str) -> bool {
         match self {
             Self::Regex(regex) => regex.is_match(value),
             Self::String(ref s) => value.contains(s),
+            Self::Glob(ref glob) => glob_matches(glob, value),
         }
     }
 
+    /// Find possible secrets within `haystack`. Each capture is a byte offset
+    /// into `haystack` and the captured text. An empty [`Vec`] means there was
+    /// no match.
+    ///
+    /// What gets captured depends on the kind of pattern:
+    /// - [`Pattern::Regex`] returns the entire first match (group `0`)
+    ///   followed by every capture group that participated in it, in group
+    ///   order. Rules converted from gitleaks rely on this, since their
+    ///   secret is usually in a capture group rather than the whole match.
+    ///   Groups that captured only whitespace are skipped, and a group that
+    ///   captured the same text at the same offset as the previous one is
+    ///   only returned once.
+    /// - [`Pattern::String`] returns the whitespace- or `;`-delimited word
+    ///   containing the first occurrence of the string.
+    /// - [`Pattern::Glob`] returns all of `haystack` when it matches.
+    ///
+    /// Use [`Pattern::capture_at`] to only capture one group.
+    ///
+    /// ## Example
+    /// ```
+    /// use keyhunter::Pattern;
+    /// use regex::Regex;
+    ///
+    /// let pattern = Pattern::from(Regex::new(r"token=(\w+)").unwrap());
+    /// assert_eq!(
+    ///     pattern.captures("token=abc123"),
+    ///     vec![(0, "token=abc123"), (6, "abc123")]
+    /// );
+    /// ```
     pub fn captures<'s>(&self, haystack: &'s str) -> Vec<(usize, &'s str)> {
+        self.captures_with_groups(haystack)
+            .into_iter()
+            .map(|(start, found, _)| (start, found))
+            .collect()
+    }
+
+    /// Same as [`Pattern::captures`], but also returns the index of the regex
+    /// capture group each capture came from. Captures shared by several
+    /// groups are attributed to the first one. Always [`None`] for string and
+    /// glob patterns, which have no groups.
+    pub(crate) fn captures_with_groups<'s>(
+        &self,
+        haystack: &'s str,
+    ) -> Vec<(usize, &'s str, Option<usize>)> {
         match self {
             Self::Regex(regex) => {
                 let Some(captures) = regex.captures(haystack) else {
@@ -76,7 +178,8 @@ impl Pattern {
 
                 let mut found_keys = captures
                     .iter()
-                    .filter_map(|cap| {
+                    .enumerate()
+                    .filter_map(|(group, cap)| {
                         let cap = cap?;
                         let found = cap.as_str();
 
@@ -84,12 +187,12 @@ impl Pattern {
                         if found.trim().is_empty() {
                             None
                         } else {
-                            Some((cap.start(), found))
+                            Some((cap.start(), found, Some(group)))
                         }
                     })
                     .collect::<Vec<_>>();
 
-                found_keys.dedup();
+                found_keys.dedup_by(|later, earlier| later.0 == earlier.0 && later.1 == earlier.1);
                 found_keys
             }
             Self::String(s) => {
@@ -124,12 +227,284 @@ impl Pattern {
                 }
                 let start = start - i;
 
-                vec![(start, &haystack[start..end])]
+                vec![(start, &haystack[start..end], None)]
+            }
+            Self::Glob(glob) => {
+                if glob_matches(glob, haystack) {
+                    vec![(0, haystack, None)]
+                } else {
+                    vec![]
+                }
             }
         }
     }
 
+    /// Find the secret captured by regex group `group` in the first match
+    /// within `haystack`, as a byte offset and the captured text. Group `0`
+    /// is the entire match.
+    ///
+    /// Returns [`None`] when there is no match, `group` does not exist or did
+    /// not participate in the match, or it only captured whitespace. String
+    /// and glob patterns have no groups, so their only capture is returned
+    /// regardless of `group`.
+    ///
+    /// ## Example
+    /// ```
+    /// use keyhunter::Pattern;
+    /// use regex::Regex;
+    ///
+    /// let pattern = Pattern::from(Regex::new(r"(\w+)_key=(\w+)").unwrap());
+    /// assert_eq!(pattern.capture_at("acme_key=abc123", 2), Some((9, "abc123")));
+    /// assert_eq!(pattern.capture_at("acme_key=abc123", 3), None);
+    /// ```
+    pub fn capture_at<'s>(&self, haystack: &'s str, group: usize) -> Option<(usize, &'s str)> {
+        self.capture_with_group_at(haystack, group)
+            .map(|(start, found, _)| (start, found))
+    }
+
+    /// Same as [`Pattern::capture_at`], but also returns `group` for regex
+    /// patterns, like [`Pattern::captures_with_groups`].
+    pub(crate) fn capture_with_group_at<'s>(
+        &self,
+        haystack: &'s str,
+        group: usize,
+    ) -> Option<(usize, &'s str, Option<usize>)> {
+        let Self::Regex(regex) = self else {
+            return self.captures_with_groups(haystack).into_iter().next();
+        };
+
+        let cap = regex.captures(haystack)?.get(group)?;
+        let found = cap.as_str();
+        // empty strings are never API keys
+        if found.trim().is_empty() {
+            None
+        } else {
+            Some((cap.start(), found, Some(group)))
+        }
+    }
+
     fn is_break_char(c: char) -> bool {
         c.is_whitespace() || c == ';'
     }
 }
+
+/// Translate a glob into an equivalent regex that matches entire inputs.
+fn glob_to_regex(glob: &str) -> String {
+    let mut regex = String::with_capacity(glob.len() + 8);
+    regex.push_str("^(?s:");
+    for c in glob.chars() {
+        match c {
+            '*' => regex.push_str(".*"),
+            '?' => regex.push('.'),
+            c => regex.push_str(&regex::escape(c.encode_utf8(&mut [0; 4]))),
+        }
+    }
+    regex.push_str(")$");
+    regex
+}
+
+/// Returns `true` if `glob` matches the entirety of `value`.
+///
+/// Uses a greedy algorithm that backtracks to the most recent `*` on a
+/// mismatch, which avoids exponential blowup on patterns with many wildcards.
+fn glob_matches(glob: &str, value: &str) -> bool {
+    let glob: Vec<char> = glob.chars().collect();
+    let value: Vec<char> = value.chars().collect();
+
+    let (mut g, mut v) = (0, 0);
+    // (glob index after the last `*`, value index that `*` is matched up to)
+    let mut backtrack: Option<(usize, usize)> = None;
+
+    while v < value.len() {
+        match glob.get(g) {
+            Some('*') => {
+                g += 1;
+                backtrack = Some((g, v));
+            }
+            Some('?') => {
+                g += 1;
+                v += 1;
+            }
+            Some(&c) if c == value[v] => {
+                g += 1;
+                v += 1;
+            }
+            _ => match backtrack {
+                // let the last `*` consume one more character and try again
+                Some((star_g, star_v)) => {
+                    g = star_g;
+                    v = star_v + 1;
+                    backtrack = Some((star_g, star_v + 1));
+                }
+                None => return false,
+            },
+        }
+    }
+
+    // trailing `*`s match the empty string
+    glob[g..].iter().all(|&c| c == '*')
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_substring() {
+        let pattern = Pattern::from("secret");
+        assert!(pattern.matches("secret"));
+        assert!(pattern.matches("client_secret"));
+        assert!(pattern.matches("secret_key"));
+        assert!(!pattern.matches("SECRET"));
+        assert!(!pattern.matches("client_id"));
+    }
+
+    #[test]
+    fn test_glob() {
+        let pattern = Pattern::glob("*secret*");
+        for yes in ["secret", "client_secret", "secret_key", "my_secret_key"] {
+            assert!(pattern.matches(yes), "'{yes}' should match {pattern}");
+        }
+        for no in ["", "client_id", "SECRET", "secre"] {
+            assert!(!pattern.matches(no), "'{no}' should not match {pattern}");
+        }
+
+        // globs are anchored to the start and end of the input
+        let pattern = Pattern::glob("api_*");
+        assert!(pattern.matches("api_key"));
+        assert!(pattern.matches("api_"));
+        assert!(!pattern.matches("x_api_key"));
+
+        let pattern = Pattern::glob("*_key");
+        assert!(pattern.matches("api_key"));
+        assert!(!pattern.matches("api_key_id"));
+
+        let pattern = Pattern::glob("k?y");
+        assert!(pattern.matches("key"));
+        assert!(pattern.matches("kay"));
+        assert!(!pattern.matches("ky"));
+        assert!(!pattern.matches("keey"));
+
+        let pattern = Pattern::glob("*a*b*c");
+        assert!(pattern.matches("abc"));
+        assert!(pattern.matches("xxaxxbxxc"));
+        assert!(!pattern.matches("xxaxxbxxcx"));
+        assert!(!pattern.matches("cba"));
+    }
+
+    #[test]
+    fn test_regex_captures_multiple_groups() {
+        let pattern = Pattern::from(Regex::new(r"(\w+)_(key|token)=(\w+)").unwrap());
+        assert_eq!(
+            pattern.captures("stripe_key=sk_live_1234"),
+            vec![
+                (0, "stripe_key=sk_live_1234"),
+                (0, "stripe"),
+                (7, "key"),
+                (11, "sk_live_1234"),
+            ]
+        );
+        assert!(pattern.captures("nothing here").is_empty());
+    }
+
+    #[test]
+    fn test_regex_captures_skipped_groups() {
+        // optional group that does not participate
+        let pattern = Pattern::from(Regex::new(r"(?:(sk_)|pk_)(\d+)").unwrap());
+        assert_eq!(
+            pattern.captures("pk_1234"),
+            vec![(0, "pk_1234"), (3, "1234")]
+        );
+        assert_eq!(
+            pattern.captures("sk_1234"),
+            vec![(0, "sk_1234"), (0, "sk_"), (3, "1234")]
+        );
+
+        // whitespace-only groups are never secrets
+        let pattern = Pattern::from(Regex::new(r"key(\s*)=(\w+)").unwrap());
+        assert_eq!(
+            pattern.captures("key  =abc"),
+            vec![(0, "key  =abc"), (6, "abc")]
+        );
+
+        // a group spanning the whole match is not repeated
+        let pattern = Pattern::from(Regex::new(r"(AKIA\w{4})").unwrap());
+        assert_eq!(pattern.captures("x AKIA1234"), vec![(2, "AKIA1234")]);
+    }
+
+    #[test]
+    fn test_regex_captures_first_match_only() {
+        let pattern = Pattern::from(Regex::new(r"id=(\d+)").unwrap());
+        assert_eq!(pattern.captures("id=1 id=2"), vec![(0, "id=1"), (3, "1")]);
+    }
+
+    #[test]
+    fn test_into_case_insensitive() {
+        let regex = Pattern::from(Regex::new(r"sk_live_[a-z0-9]+").unwrap())
+            .into_case_insensitive()
+            .unwrap();
+        assert_eq!(
+            regex.captures("SK_LIVE_AbC123"),
+            vec![(0, "SK_LIVE_AbC123")]
+        );
+
+        let string = Pattern::from("secret.key").into_case_insensitive().unwrap();
+        assert!(string.matches("CLIENT_SECRET.KEY"));
+        assert!(!string.matches("client_secretXkey"));
+
+        let glob = Pattern::glob("*api?key*").into_case_insensitive().unwrap();
+        assert!(glob.matches("MY_API_KEY"));
+        assert!(glob.matches("my-Api-Key-2"));
+        assert!(!glob.matches("my_apikey"));
+        assert_eq!(glob.captures("MY_API_KEY"), vec![(0, "MY_API_KEY")]);
+    }
+
+    #[test]
+    fn test_captures_with_groups() {
+        let pattern = Pattern::from(Regex::new(r"key=(sk-\w+)").unwrap());
+        assert_eq!(
+            pattern.captures_with_groups("key=sk-1234"),
+            vec![(0, "key=sk-1234", Some(0)), (4, "sk-1234", Some(1))]
+        );
+        // the same capture from several groups is attributed to the first
+        let pattern = Pattern::from(Regex::new(r"((sk-\w+))").unwrap());
+        assert_eq!(
+            pattern.captures_with_groups("sk-1234"),
+            vec![(0, "sk-1234", Some(0))]
+        );
+        assert_eq!(
+            pattern.capture_with_group_at("x sk-1234", 2),
+            Some((2, "sk-1234", Some(2)))
+        );
+
+        let glob = Pattern::glob("sk-*");
+        assert_eq!(glob.captures_with_groups("sk-1"), vec![(0, "sk-1", None)]);
+        assert_eq!(
+            glob.capture_with_group_at("sk-1", 1),
+            Some((0, "sk-1", None))
+        );
+    }
+
+    #[test]
+    fn test_capture_at() {
+        let pattern = Pattern::from(Regex::new(r"(?:(sk_)|pk_)(\d+)(\s*)").unwrap());
+        assert_eq!(pattern.capture_at("x pk_1234", 0), Some((2, "pk_1234")));
+        assert_eq!(pattern.capture_at("x pk_1234", 2), Some((5, "1234")));
+        // groups that did not participate, only captured whitespace, or do
+        // not exist
+        assert_eq!(pattern.capture_at("x pk_1234", 1), None);
+        assert_eq!(pattern.capture_at("x pk_1234 ", 3), None);
+        assert_eq!(pattern.capture_at("x pk_1234", 4), None);
+        assert_eq!(pattern.capture_at("nothing", 0), None);
+
+        let glob = Pattern::glob("sk-*");
+        assert_eq!(glob.capture_at("sk-1234", 2), Some((0, "sk-1234")));
+    }
+
+    #[test]
+    fn test_glob_captures() {
+        let pattern = Pattern::glob("sk-*");
+        assert_eq!(pattern.captures("sk-1234"), vec![(0, "sk-1234")]);
+        assert!(pattern.captures("pk-1234").is_empty());
+    }
+}
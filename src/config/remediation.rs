@@ -0,0 +1,167 @@
+/// Copyright © 2024 Don Isaac
+///
+/// This file is part of KeyHunter.
+///
+/// KeyHunter is free software: you can redistribute it and/or modify it
+/// under the terms of the GNU General Public License as published by the Free
+/// Software Foundation, either version 3 of the License, or (at your option)
+/// any later version.
+///
+/// KeyHunter is distributed in the hope that it will be useful, but WITHOUT
+/// ANY WARRANTY; without even the implied warranty of MERCHANTABILITY or
+/// FITNESS FOR A PARTICULAR PURPOSE. See the GNU General Public License for
+/// more details.
+///
+/// You should have received a copy of the GNU General Public License along with
+/// KeyHunter. If not, see <https://www.gnu.org/licenses/>.
+
+/// Advice for rules from well-known providers, keyed by rule ID prefix.
+///
+/// Entries are checked in order, so more specific prefixes come first.
+const DEFAULT_REMEDIATIONS: &[(&str, &str)] = &[
+    (
+        "aws",
+        "Deactivate the access key in the AWS IAM console, create a new one, and \
+         check CloudTrail for unauthorized use.",
+    ),
+    (
+        "gcp",
+        "Delete or regenerate the key in the Google Cloud console and restrict \
+         replacements to the APIs and referrers that need them.",
+    ),
+    (
+        "github",
+        "Revoke the token in GitHub's developer settings and review the \
+         account's security log for unauthorized use.",
+    ),
+    (
+        "gitlab",
+        "Revoke the token in GitLab's access token settings and create a new \
+         one with the minimum required scopes.",
+    ),
+    (
+        "slack-webhook",
+        "Regenerate the webhook URL in your Slack app's Incoming Webhooks \
+         settings.",
+    ),
+    (
+        "slack",
+        "Revoke the token from your Slack app's OAuth & Permissions page and \
+         reinstall the app to issue a new one.",
+    ),
+    (
+        "stripe",
+        "Roll the key in the Stripe dashboard. Only publishable keys \
+         (pk_live_, pk_test_) are safe to use in client-side code.",
+    ),
+    (
+        "twilio",
+        "Delete the API key in the Twilio console and create a new one.",
+    ),
+    (
+        "sendgrid",
+        "Delete the API key in SendGrid's settings and create a new one with \
+         restricted access.",
+    ),
+    (
+        "openai",
+        "Revoke the key in the OpenAI dashboard. Call OpenAI from a server \
+         instead of from client-side code.",
+    ),
+    (
+        "npm",
+        "Revoke the token with `npm token revoke` and check the packages it \
+         could publish for unexpected releases.",
+    ),
+    (
+        "private-key",
+        "Treat the key pair as compromised: replace it everywhere it is \
+         trusted, and never serve private keys to browsers.",
+    ),
+];
+
+/// Where to revoke or rotate keys for well-known providers, keyed by rule ID
+/// prefix.
+#[cfg(feature = "report")]
+const ROTATION_URLS: &[(&str, &str)] = &[
+    (
+        "aws",
+        "https://console.aws.amazon.com/iam/home#/security_credentials",
+    ),
+    ("gcp", "https://console.cloud.google.com/apis/credentials"),
+    ("github", "https://github.com/settings/tokens"),
+    (
+        "gitlab",
+        "https://gitlab.com/-/user_settings/personal_access_tokens",
+    ),
+    ("slack", "https://api.slack.com/apps"),
+    ("stripe", "https://dashboard.stripe.com/apikeys"),
+    (
+        "twilio",
+        "https://console.twilio.com/us1/account/keys-credentials/api-keys",
+    ),
+    ("sendgrid", "https://app.sendgrid.com/settings/api_keys"),
+    ("openai", "https://platform.openai.com/api-keys"),
+    ("npm", "https://www.npmjs.com/settings/~/tokens"),
+];
+
+/// Advice given when no provider-specific remediation is known.
+const GENERIC_REMEDIATION: &str =
+    "Rotate the secret with its provider and move it out of client-side code, e.g. \
+     into a secrets manager or a server-side proxy.";
+
+/// Get the default remediation advice for the rule with display ID `rule_id`.
+///
+/// Rules from known providers get provider-specific advice. Everything else
+/// gets generic advice.
+pub(super) fn default_remediation(rule_id: &str) -> &'static str {
+    find_by_prefix(DEFAULT_REMEDIATIONS, rule_id).unwrap_or(GENERIC_REMEDIATION)
+}
+
+/// Get the page where keys found by the rule with display ID `rule_id` can be
+/// revoked or rotated, if it is for a known provider.
+#[cfg(feature = "report")]
+pub(crate) fn rotation_url(rule_id: &str) -> Option<&'static str> {
+    find_by_prefix(ROTATION_URLS, rule_id)
+}
+
+/// Find the first entry whose prefix matches `rule_id`. Prefixes only match
+/// whole `-`-separated segments.
+fn find_by_prefix(table: &[(&str, &'static str)], rule_id: &str) -> Option<&'static str> {
+    table
+        .iter()
+        .find(|(prefix, _)| {
+            rule_id
+                .strip_prefix(prefix)
+                .is_some_and(|rest| rest.is_empty() || rest.starts_with('-'))
+        })
+        .map(|(_, value)| *value)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_default_remediation() {
+        assert!(default_remediation("aws-access-token").contains("AWS IAM"));
+        assert!(default_remediation("slack-webhook-url").contains("webhook URL"));
+        assert!(default_remediation("slack-bot-token").contains("OAuth"));
+        assert!(default_remediation("private-key").contains("key pair"));
+        // prefixes only match whole segments
+        assert_eq!(default_remediation("awsome-token"), GENERIC_REMEDIATION);
+        assert_eq!(default_remediation("acme-key"), GENERIC_REMEDIATION);
+    }
+
+    #[test]
+    #[cfg(feature = "report")]
+    fn test_rotation_url() {
+        assert_eq!(
+            rotation_url("github-pat"),
+            Some("https://github.com/settings/tokens")
+        );
+        assert!(rotation_url("stripe-access-token").is_some());
+        assert_eq!(rotation_url("generic-api-key"), None);
+        assert_eq!(rotation_url("npmjs-token"), None);
+    }
+}
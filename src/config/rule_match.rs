@@ -25,27 +25,62 @@ impl Config {
         name_criteria.matches(identifier_name)
     }
 
+    /// Returns `true` if a rule applies to scripts at `path`, e.g.
+    /// `/static/main.js`. Rules without path criteria apply to every script.
+    pub fn check_path(&self, rule_id: RuleId, path: &str) -> bool {
+        self.get_path_criteria(rule_id)
+            .map_or(true, |path_criteria| path_criteria.matches(path))
+    }
+
+    /// Find secrets within `haystack`, yielding the rule that found each one,
+    /// its byte offset, and the secret itself.
+    ///
+    /// Rules with a secret group (see [`Config::get_secret_group`]) only
+    /// report that group. Other rules report every capture returned by
+    /// [`Pattern::captures`](super::Pattern::captures).
     pub fn check_values<'c, 's: 'c>(
         &'c self,
         haystack: &'s str,
     ) -> impl Iterator<Item = (RuleId, usize, &'s str)> + 'c {
+        self.check_values_with_groups(haystack)
+            .map(|(rule_id, start, found, _)| (rule_id, start, found))
+    }
+
+    /// Same as [`Config::check_values`], but also yields the index of the
+    /// regex capture group each secret was taken from.
+    pub(crate) fn check_values_with_groups<'c, 's: 'c>(
+        &'c self,
+        haystack: &'s str,
+    ) -> impl Iterator<Item = (RuleId, usize, &'s str, Option<usize>)> + 'c {
         let collected = self
             .iter_value_criteria()
             .flat_map(|(rule_id, pat)| {
-                pat.captures(haystack)
+                let captures = match self.rule_secret_groups[rule_id] {
+                    Some(group) => pat
+                        .capture_with_group_at(haystack, group)
+                        .into_iter()
+                        .collect(),
+                    None => pat.captures_with_groups(haystack),
+                };
+                captures
                     .into_iter()
-                    .flat_map(move |cap| Some((rule_id, cap.0, cap.1)))
+                    .map(move |(start, found, group)| (rule_id, start, found, group))
             })
             .filter(|cap| {
+                if self.is_allowed_secret(cap.2) {
+                    return false;
+                }
                 // Hack for generic rules used by gitleaks
                 if self.get_display_id(cap.0).starts_with("generic") && !cap.2.contains_digit() {
                     return false;
                 }
                 if let Some(entropy) = self.rule_entropy[cap.0] {
-                    entropy <= cap.2.entropy()
-                } else {
-                    true
+                    if entropy > cap.2.entropy() {
+                        return false;
+                    }
                 }
+                self.get_validator(cap.0)
+                    .map_or(true, |validator| validator.validate(cap.2))
             });
 
         collected
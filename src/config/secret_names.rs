@@ -0,0 +1,180 @@
+/// Copyright © 2024 Don Isaac
+///
+/// This file is part of KeyHunter.
+///
+/// KeyHunter is free software: you can redistribute it and/or modify it
+/// under the terms of the GNU General Public License as published by the Free
+/// Software Foundation, either version 3 of the License, or (at your option)
+/// any later version.
+///
+/// KeyHunter is distributed in the hope that it will be useful, but WITHOUT
+/// ANY WARRANTY; without even the implied warranty of MERCHANTABILITY or
+/// FITNESS FOR A PARTICULAR PURPOSE. See the GNU General Public License for
+/// more details.
+///
+/// You should have received a copy of the GNU General Public License along with
+/// KeyHunter. If not, see <https://www.gnu.org/licenses/>.
+use regex::Regex;
+
+use super::{CustomRule, Pattern};
+
+/// Report string values assigned to secret-like names, e.g. `apiKey` or
+/// `password`, even when no rule recognizes the value itself.
+///
+/// Keywords are matched against variable and property names ignoring case
+/// and separators, so `apiKey` also matches `API_KEY`, `api-key`, and
+/// `stripeApiKey`. Values must be at least [`SecretNames::with_min_length`]
+/// characters long, contain no whitespace, and have a Shannon entropy of at
+/// least [`SecretNames::with_entropy`].
+///
+/// Found values are reported by a rule with the ID
+/// [`SecretNames::RULE_ID`]. See [`Config::with_secret_names`].
+///
+/// ## Example
+/// ```
+/// use keyhunter::{Config, SecretNames};
+///
+/// let config = Config::empty().with_secret_names(SecretNames::default());
+/// assert!(config.find_rule_id(SecretNames::RULE_ID).is_some());
+/// ```
+///
+/// [`Config::with_secret_names`]: crate::Config::with_secret_names
+#[derive(Debug, Clone, PartialEq)]
+#[must_use]
+pub struct SecretNames {
+    keywords: Vec<String>,
+    entropy: f32,
+    min_length: usize,
+}
+
+impl Default for SecretNames {
+    fn default() -> Self {
+        Self::new(Self::DEFAULT_KEYWORDS.iter().copied())
+    }
+}
+
+impl SecretNames {
+    /// ID of the rule that reports values found by name.
+    pub const RULE_ID: &'static str = "secret-name";
+    pub const DEFAULT_KEYWORDS: [&'static str; 4] = ["apiKey", "secret", "token", "password"];
+    pub const DEFAULT_ENTROPY: f32 = 3.0;
+    pub const DEFAULT_MIN_LENGTH: usize = 8;
+
+    /// Report values assigned to names containing any of `keywords`.
+    pub fn new<I, S>(keywords: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        Self {
+            keywords: keywords.into_iter().map(Into::into).collect(),
+            entropy: Self::DEFAULT_ENTROPY,
+            min_length: Self::DEFAULT_MIN_LENGTH,
+        }
+    }
+
+    pub fn keywords(&self) -> &[String] {
+        &self.keywords
+    }
+
+    /// Only report values with a Shannon entropy of at least `entropy`.
+    /// Default [`SecretNames::DEFAULT_ENTROPY`].
+    pub fn with_entropy(mut self, entropy: f32) -> Self {
+        self.entropy = entropy;
+        self
+    }
+
+    /// Only report values at least `min_length` characters long. Default
+    /// [`SecretNames::DEFAULT_MIN_LENGTH`].
+    pub fn with_min_length(mut self, min_length: usize) -> Self {
+        self.min_length = min_length.max(1);
+        self
+    }
+
+    /// Build the rule that reports values found by name. [`None`] when there
+    /// are no keywords, since an empty name pattern would match every name.
+    pub(super) fn to_rule(&self) -> Option<CustomRule> {
+        let words: Vec<String> = self
+            .keywords
+            .iter()
+            .map(|keyword| name_regex(keyword))
+            .filter(|word| !word.is_empty())
+            .collect();
+        if words.is_empty() {
+            return None;
+        }
+        let name = Regex::new(&format!("(?i)(?:{})", words.join("|"))).ok()?;
+        let value = Regex::new(&format!(r"^\S{{{},}}$", self.min_length)).ok()?;
+
+        Some(
+            CustomRule::new(Self::RULE_ID, Pattern::from(value))
+                .with_description(format!(
+                    "Found a value assigned to a secret-like name, e.g. {}",
+                    self.keywords.join(", ")
+                ))
+                .with_name(Pattern::from(name))
+                .with_entropy(self.entropy),
+        )
+    }
+}
+
+/// Split a keyword into words at separators and camelCase boundaries, then
+/// join them so any separator (or none) may appear between them. e.g.
+/// `apiKey` becomes `api[_.-]?key`.
+fn name_regex(keyword: &str) -> String {
+    let mut words: Vec<String> = vec![];
+    let mut word = String::new();
+    let mut prev_lowercase = false;
+    for c in keyword.chars() {
+        if !c.is_alphanumeric() {
+            words.push(std::mem::take(&mut word));
+            prev_lowercase = false;
+            continue;
+        }
+        if c.is_uppercase() && prev_lowercase {
+            words.push(std::mem::take(&mut word));
+        }
+        prev_lowercase = c.is_lowercase() || c.is_ascii_digit();
+        word.extend(c.to_lowercase());
+    }
+    words.push(word);
+
+    words
+        .iter()
+        .filter(|word| !word.is_empty())
+        .map(|word| regex::escape(word))
+        .collect::<Vec<_>>()
+        .join("[_.-]?")
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_name_regex() {
+        assert_eq!(name_regex("secret"), "secret");
+        assert_eq!(name_regex("apiKey"), "api[_.-]?key");
+        assert_eq!(name_regex("API_KEY"), "api[_.-]?key");
+        assert_eq!(name_regex("client-secret"), "client[_.-]?secret");
+        assert_eq!(name_regex("__"), "");
+
+        let rule = SecretNames::default().to_rule().unwrap();
+        let name = rule.name.unwrap();
+        for matching in [
+            "apiKey",
+            "API_KEY",
+            "stripe_api_key",
+            "clientSecret",
+            "authToken",
+        ] {
+            assert!(name.matches(matching), "{matching}");
+        }
+        for other in ["apiUrl", "greeting", "keyboard"] {
+            assert!(!name.matches(other), "{other}");
+        }
+
+        assert!(SecretNames::new(Vec::<String>::new()).to_rule().is_none());
+        assert!(SecretNames::new(["--"]).to_rule().is_none());
+    }
+}
@@ -0,0 +1,149 @@
+/// Copyright © 2024 Don Isaac
+///
+/// This file is part of KeyHunter.
+///
+/// KeyHunter is free software: you can redistribute it and/or modify it
+/// under the terms of the GNU General Public License as published by the Free
+/// Software Foundation, either version 3 of the License, or (at your option)
+/// any later version.
+///
+/// KeyHunter is distributed in the hope that it will be useful, but WITHOUT
+/// ANY WARRANTY; without even the implied warranty of MERCHANTABILITY or
+/// FITNESS FOR A PARTICULAR PURPOSE. See the GNU General Public License for
+/// more details.
+///
+/// You should have received a copy of the GNU General Public License along with
+/// KeyHunter. If not, see <https://www.gnu.org/licenses/>.
+use miette::{miette, IntoDiagnostic as _, Result};
+use yaml_rust2::{Yaml, YamlLoader};
+
+/// A set of [custom detectors](https://github.com/trufflesecurity/trufflehog#regex-detector-alpha)
+/// in trufflehog's YAML format.
+///
+/// ```yaml
+/// detectors:
+///   - name: HogTokenDetector
+///     keywords:
+///       - hog
+///     regex:
+///       hogID: '\b(HOG[0-9A-Z]{17})\b'
+/// ```
+///
+/// Verification endpoints are ignored.
+#[derive(Debug)]
+pub struct TruffleHogConfig {
+    pub detectors: Vec<TruffleHogDetector>,
+}
+
+#[derive(Debug)]
+pub struct TruffleHogDetector {
+    pub name: String,
+    pub keywords: Vec<String>,
+    /// Named regexes, in the order they were declared.
+    pub regex: Vec<(String, String)>,
+}
+
+impl TruffleHogConfig {
+    pub fn parse(source_text: &str) -> Result<Self> {
+        let docs = YamlLoader::load_from_str(source_text).into_diagnostic()?;
+        let Some(doc) = docs.first() else {
+            return Ok(Self { detectors: vec![] });
+        };
+
+        let detectors = match &doc["detectors"] {
+            Yaml::Array(detectors) => detectors
+                .iter()
+                .enumerate()
+                .map(|(i, detector)| TruffleHogDetector::parse(i, detector))
+                .collect::<Result<_>>()?,
+            Yaml::BadValue => vec![],
+            _ => return Err(miette!("`detectors` must be a list")),
+        };
+
+        Ok(Self { detectors })
+    }
+}
+
+impl TruffleHogDetector {
+    fn parse(index: usize, detector: &Yaml) -> Result<Self> {
+        let name = detector["name"]
+            .as_str()
+            .ok_or_else(|| miette!("Detector {index} is missing a `name`"))?
+            .to_string();
+
+        let keywords = match &detector["keywords"] {
+            Yaml::Array(keywords) => keywords
+                .iter()
+                .map(|keyword| {
+                    keyword
+                        .as_str()
+                        .map(str::to_string)
+                        .ok_or_else(|| miette!("Keywords for detector {name} must be strings"))
+                })
+                .collect::<Result<_>>()?,
+            Yaml::BadValue => vec![],
+            _ => return Err(miette!("Keywords for detector {name} must be a list")),
+        };
+
+        let Yaml::Hash(regexes) = &detector["regex"] else {
+            return Err(miette!(
+                "Detector {name} must have a `regex` map of names to patterns"
+            ));
+        };
+        let regex = regexes
+            .iter()
+            .map(|(key, pattern)| match (key.as_str(), pattern.as_str()) {
+                (Some(key), Some(pattern)) => Ok((key.to_string(), pattern.to_string())),
+                _ => Err(miette!("Regexes for detector {name} must be strings")),
+            })
+            .collect::<Result<_>>()?;
+
+        Ok(Self {
+            name,
+            keywords,
+            regex,
+        })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_parse() {
+        let text = r#"
+detectors:
+  - name: HogTokenDetector
+    keywords:
+      - hog
+    regex:
+      hogID: '\b(HOG[0-9A-Z]{17})\b'
+      hogToken: '[^A-Za-z0-9+\/]{0,1}([A-Za-z0-9+\/]{40})[^A-Za-z0-9+\/]{0,1}'
+    verify:
+      - endpoint: http://localhost:8000/
+        unsafe: true
+"#;
+        let config = TruffleHogConfig::parse(text).unwrap();
+        assert_eq!(config.detectors.len(), 1);
+        let detector = &config.detectors[0];
+        assert_eq!(detector.name, "HogTokenDetector");
+        assert_eq!(detector.keywords, ["hog"]);
+        let names: Vec<_> = detector
+            .regex
+            .iter()
+            .map(|(name, _)| name.as_str())
+            .collect();
+        assert_eq!(names, ["hogID", "hogToken"]);
+        assert_eq!(detector.regex[0].1, r"\b(HOG[0-9A-Z]{17})\b");
+    }
+
+    #[test]
+    fn test_parse_invalid() {
+        assert!(TruffleHogConfig::parse("").unwrap().detectors.is_empty());
+        assert!(TruffleHogConfig::parse("detectors: 1").is_err());
+        assert!(TruffleHogConfig::parse("detectors:\n  - keywords: [a]").is_err());
+        assert!(TruffleHogConfig::parse("detectors:\n  - name: NoRegex").is_err());
+        assert!(TruffleHogConfig::parse("detectors: [").is_err());
+    }
+}
@@ -0,0 +1,184 @@
+/// Copyright © 2024 Don Isaac
+///
+/// This file is part of KeyHunter.
+///
+/// KeyHunter is free software: you can redistribute it and/or modify it
+/// under the terms of the GNU General Public License as published by the Free
+/// Software Foundation, either version 3 of the License, or (at your option)
+/// any later version.
+///
+/// KeyHunter is distributed in the hope that it will be useful, but WITHOUT
+/// ANY WARRANTY; without even the implied warranty of MERCHANTABILITY or
+/// FITNESS FOR A PARTICULAR PURPOSE. See the GNU General Public License for
+/// more details.
+///
+/// You should have received a copy of the GNU General Public License along with
+/// KeyHunter. If not, see <https://www.gnu.org/licenses/>.
+use std::{fmt, io::Read as _};
+
+use ureq::{Agent, Request};
+use url::Url;
+
+use super::error::DownloadScriptDiagnostic;
+use crate::http::Credentials;
+
+/// The `Content-Type` header of a downloaded resource, if it had one.
+pub type ContentType = Option<String>;
+
+/// Fetches the scripts and JSON files checked by an [`ApiKeyCollector`].
+///
+/// [`UreqDownloader`] is used by default. Provide your own implementation with
+/// [`ApiKeyCollector::with_downloader`] to use a different HTTP client, a
+/// custom authentication scheme, or to serve canned responses in tests.
+///
+/// Implementations should not check content types; the collector does that
+/// itself, sniffing bodies when the content type is generic or missing.
+///
+/// [`ApiKeyCollector`]: crate::ApiKeyCollector
+/// [`ApiKeyCollector::with_downloader`]: crate::ApiKeyCollector::with_downloader
+pub trait Downloader: fmt::Debug + Send + Sync {
+    /// Download the resource at `url`, returning its body and content type.
+    fn get(&self, url: &Url) -> Result<(String, ContentType), DownloadScriptDiagnostic>;
+
+    /// Download the resource at `url` without decoding it as text. Used for
+    /// precompressed scripts, e.g. `main.js.gz`.
+    ///
+    /// Defaults to [`Downloader::get`], which only works for text.
+    fn get_bytes(&self, url: &Url) -> Result<(Vec<u8>, ContentType), DownloadScriptDiagnostic> {
+        self.get(url)
+            .map(|(body, content_type)| (body.into_bytes(), content_type))
+    }
+
+    /// Download the resource at `url` with a `Referer` header, e.g. the URL of
+    /// the page a script was found on.
+    ///
+    /// Defaults to [`Downloader::get`], ignoring `referer`.
+    fn get_with_referer(
+        &self,
+        url: &Url,
+        referer: &str,
+    ) -> Result<(String, ContentType), DownloadScriptDiagnostic> {
+        let _ = referer;
+        self.get(url)
+    }
+
+    /// Like [`Downloader::get_bytes`], but with a `Referer` header.
+    ///
+    /// Defaults to [`Downloader::get_bytes`], ignoring `referer`.
+    fn get_bytes_with_referer(
+        &self,
+        url: &Url,
+        referer: &str,
+    ) -> Result<(Vec<u8>, ContentType), DownloadScriptDiagnostic> {
+        let _ = referer;
+        self.get_bytes(url)
+    }
+}
+
+/// The default [`Downloader`], backed by a [`ureq`] [`Agent`].
+#[derive(Debug, Clone)]
+pub struct UreqDownloader {
+    pub(crate) agent: Agent,
+    /// `User-Agent` header sent with each request
+    pub(crate) ua: Option<&'static str>,
+    /// Other headers to include in requests
+    pub(crate) headers: Vec<(String, String)>,
+    /// `Authorization` credentials, each scoped to a domain
+    pub(crate) credentials: Vec<Credentials>,
+}
+
+/// Largest script that is downloaded or decompressed, in bytes. Same limit
+/// [`ureq::Response::into_string`] uses.
+pub(crate) const MAX_SCRIPT_SIZE: u64 = 10 * 1024 * 1024;
+
+impl UreqDownloader {
+    pub fn new(agent: Agent) -> Self {
+        Self {
+            agent,
+            ua: None,
+            headers: vec![],
+            credentials: vec![],
+        }
+    }
+
+    pub(crate) const REFERER: &'static str = "Referer";
+
+    /// `referer` replaces any `Referer` set in `headers`.
+    fn request(&self, url: &Url, referer: Option<&str>) -> Request {
+        let request = self.agent.get(url.as_str());
+
+        let request = if let Some(ua) = self.ua {
+            request.set("User-Agent", ua)
+        } else {
+            request
+        };
+        let request = self
+            .headers
+            .iter()
+            .fold(request, |req, (key, value)| req.set(key, value));
+        let request = match referer {
+            Some(referer) => request.set(Self::REFERER, referer),
+            None => request,
+        };
+        match self.credentials.iter().find(|auth| auth.applies_to(url)) {
+            Some(auth) => request.set(Credentials::HEADER, auth.header()),
+            None => request,
+        }
+    }
+
+    fn fetch(
+        &self,
+        url: &Url,
+        referer: Option<&str>,
+    ) -> Result<(String, ContentType), DownloadScriptDiagnostic> {
+        let res = self.request(url, referer).call()?;
+        let content_type = res.header("Content-Type").map(str::to_string);
+        let body = res
+            .into_string()
+            .map_err(|e| DownloadScriptDiagnostic::CannotReadBody(url.to_string(), e))?;
+
+        Ok((body, content_type))
+    }
+
+    fn fetch_bytes(
+        &self,
+        url: &Url,
+        referer: Option<&str>,
+    ) -> Result<(Vec<u8>, ContentType), DownloadScriptDiagnostic> {
+        let res = self.request(url, referer).call()?;
+        let content_type = res.header("Content-Type").map(str::to_string);
+        let mut body = vec![];
+        res.into_reader()
+            .take(MAX_SCRIPT_SIZE)
+            .read_to_end(&mut body)
+            .map_err(|e| DownloadScriptDiagnostic::CannotReadBody(url.to_string(), e))?;
+
+        Ok((body, content_type))
+    }
+}
+
+impl Downloader for UreqDownloader {
+    fn get(&self, url: &Url) -> Result<(String, ContentType), DownloadScriptDiagnostic> {
+        self.fetch(url, None)
+    }
+
+    fn get_bytes(&self, url: &Url) -> Result<(Vec<u8>, ContentType), DownloadScriptDiagnostic> {
+        self.fetch_bytes(url, None)
+    }
+
+    fn get_with_referer(
+        &self,
+        url: &Url,
+        referer: &str,
+    ) -> Result<(String, ContentType), DownloadScriptDiagnostic> {
+        self.fetch(url, Some(referer))
+    }
+
+    fn get_bytes_with_referer(
+        &self,
+        url: &Url,
+        referer: &str,
+    ) -> Result<(Vec<u8>, ContentType), DownloadScriptDiagnostic> {
+        self.fetch_bytes(url, Some(referer))
+    }
+}
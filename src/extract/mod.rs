@@ -1,10 +1,17 @@
 mod collector;
+mod downloader;
 mod error;
 mod extractor;
+mod finding;
 mod util;
 mod visit;
 
 pub use collector::{ApiKeyCollector, ApiKeyMessage, ApiKeyReceiver, ApiKeySender};
-pub use error::ApiKeyError;
+pub use downloader::{ContentType, Downloader, UreqDownloader};
+pub use error::{
+    ApiKeyError, DownloadScriptDiagnostic, ExtractionTimeoutDiagnostic, FailureKind, ScriptKind,
+};
 pub use extractor::ApiKeyExtractor;
+pub use finding::Finding;
 use visit::ApiKey;
+pub use visit::ScannedString;
@@ -0,0 +1,169 @@
+/// Copyright © 2024 Don Isaac
+///
+/// This file is part of KeyHunter.
+///
+/// KeyHunter is free software: you can redistribute it and/or modify it
+/// under the terms of the GNU General Public License as published by the Free
+/// Software Foundation, either version 3 of the License, or (at your option)
+/// any later version.
+///
+/// KeyHunter is distributed in the hope that it will be useful, but WITHOUT
+/// ANY WARRANTY; without even the implied warranty of MERCHANTABILITY or
+/// FITNESS FOR A PARTICULAR PURPOSE. See the GNU General Public License for
+/// more details.
+///
+/// You should have received a copy of the GNU General Public License along with
+/// KeyHunter. If not, see <https://www.gnu.org/licenses/>.
+use oxc::ast::{ast::*, visit::walk, Visit};
+use oxc::span::{Atom, Span};
+
+use super::atom_as_source_str;
+
+/// Static files that are not interesting as API endpoints.
+const ASSET_EXTENSIONS: [&str; 12] = [
+    ".png", ".jpg", ".jpeg", ".gif", ".svg", ".ico", ".webp", ".css", ".woff", ".woff2", ".ttf",
+    ".map",
+];
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Endpoint<'a> {
+    /// Where the endpoint was found in the source code.
+    pub span: Span,
+    /// An absolute URL (e.g. `https://api.example.com/v1`) or a path (e.g.
+    /// `/api/v1/users`).
+    pub url: &'a str,
+}
+
+/// Collects string literals that look like URLs or API paths.
+#[derive(Debug, Default)]
+pub(in crate::extract) struct EndpointVisitor<'a> {
+    endpoints: Vec<Endpoint<'a>>,
+}
+
+impl<'a> EndpointVisitor<'a> {
+    pub fn into_inner(self) -> Vec<Endpoint<'a>> {
+        self.endpoints
+    }
+
+    fn record_if_endpoint(&mut self, maybe_endpoint: &Atom<'a>, span: Span) {
+        let url = atom_as_source_str(maybe_endpoint);
+        if looks_like_endpoint(url) {
+            self.endpoints.push(Endpoint { span, url });
+        }
+    }
+}
+
+impl<'a> Visit<'a> for EndpointVisitor<'a> {
+    fn visit_string_literal(&mut self, lit: &StringLiteral<'a>) {
+        self.record_if_endpoint(&lit.value, lit.span);
+    }
+
+    fn visit_template_literal(&mut self, lit: &TemplateLiteral<'a>) {
+        // for templates like `/api/users/${id}`, the static prefix is still
+        // useful
+        if let Some(head) = lit.quasis.first() {
+            let value = head.value.cooked.as_ref().unwrap_or(&head.value.raw);
+            self.record_if_endpoint(value, head.span);
+        }
+        walk::walk_template_literal(self, lit);
+    }
+}
+
+fn looks_like_endpoint(s: &str) -> bool {
+    let rest = if let Some(rest) = ["https://", "http://", "wss://", "ws://"]
+        .iter()
+        .find_map(|scheme| s.strip_prefix(scheme))
+    {
+        // must have a host
+        if rest.is_empty() {
+            return false;
+        }
+        rest
+    } else if let Some(path) = s.strip_prefix('/') {
+        // `//` is a protocol-relative URL or a comment, and paths must start
+        // with a segment, e.g. `/api` but not `/` or `/*`.
+        if !path.starts_with(|c: char| c.is_ascii_alphanumeric() || c == '_') {
+            return false;
+        }
+        path
+    } else {
+        return false;
+    };
+
+    let is_url_safe = rest
+        .bytes()
+        .all(|b| b.is_ascii_alphanumeric() || b"-._~:/?#[]@!$&'()*+,;=%".contains(&b));
+    if !is_url_safe {
+        return false;
+    }
+
+    let path = rest.split(['?', '#']).next().unwrap_or_default();
+    !ASSET_EXTENSIONS.iter().any(|ext| path.ends_with(ext))
+}
+
+#[cfg(test)]
+mod test {
+    use oxc::{allocator::Allocator, parser::Parser, span::SourceType};
+
+    use super::*;
+
+    fn find_endpoints(source: &str) -> Vec<String> {
+        let alloc = Allocator::default();
+        let program = Parser::new(&alloc, source, SourceType::default())
+            .parse()
+            .program;
+        let mut visitor = EndpointVisitor::default();
+        visitor.visit_program(&program);
+        visitor
+            .into_inner()
+            .into_iter()
+            .map(|endpoint| endpoint.url.to_string())
+            .collect()
+    }
+
+    #[test]
+    fn test_looks_like_endpoint() {
+        let pass = [
+            "/api/v1/users",
+            "/graphql",
+            "/users?page=2",
+            "https://api.example.com",
+            "https://api.example.com/v1/charges",
+            "wss://example.com/socket",
+        ];
+        let fail = [
+            "",
+            "/",
+            "//",
+            "/*",
+            "https://",
+            "hello world",
+            "/path with spaces",
+            "api/v1/users",
+            "text/html",
+            "/static/logo.png",
+            "https://example.com/main.css?v=2",
+        ];
+        for s in pass {
+            assert!(looks_like_endpoint(s), "{s} should be an endpoint");
+        }
+        for s in fail {
+            assert!(!looks_like_endpoint(s), "{s} should not be an endpoint");
+        }
+    }
+
+    #[test]
+    fn test_fetch() {
+        let endpoints = find_endpoints(
+            r#"
+            fetch("/api/v1/users").then((res) => res.json());
+            axios.post(`https://api.example.com/v2/orders/${id}`, { id });
+            const mime = "application/json";
+            "#,
+        );
+        assert_eq!(
+            endpoints,
+            ["/api/v1/users", "https://api.example.com/v2/orders/"]
+        );
+    }
+}
@@ -90,6 +90,8 @@ impl<'a> GetIdentifier<'a> for PropertyKey<'a> {
             Self::Identifier(ident) => ident.get_identifier_name(),
             Self::PrivateIdentifier(ident) => ident.get_identifier_name(),
             Self::StaticIdentifier(ident) => ident.get_identifier_name(),
+            // quoted keys, e.g. in JSON
+            Self::StringLiteral(lit) => Some(lit.value.clone()),
             expr @ match_expression!(Self) => expr.to_expression().get_identifier_name(),
         }
     }
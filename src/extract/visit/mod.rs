@@ -15,20 +15,26 @@
 /// You should have received a copy of the GNU General Public License along with
 /// KeyHunter. If not, see <https://www.gnu.org/licenses/>.
 // mod api_key_check;
+mod endpoint;
 mod ident_name;
 mod string;
 
 use std::fmt;
 
+use oxc::allocator::Allocator;
 use oxc::ast::visit::walk;
 use oxc::ast::{ast::*, Visit};
 use oxc::span::{Atom, Span};
 
+use super::extractor::StringObserver;
 use crate::config::RuleId;
 use crate::Config;
 
+pub use endpoint::Endpoint;
+pub(super) use endpoint::EndpointVisitor;
 // use api_key_check::IsApiKeyName;
 use ident_name::GetIdentifier as _;
+use string::{raw_offset, GetStrValue as _};
 
 /// An API key found in a JavaScript file based on some rule.
 #[derive(Debug)]
@@ -42,6 +48,30 @@ pub struct ApiKey<'a> {
     /// The variable or property name that the secret was found in. Only present
     /// when secrets are assigned to a variable or property.
     pub key_name: Option<&'a str>,
+    /// Index of the regex capture group the secret was taken from. [`None`]
+    /// for rules that do not use a regex.
+    pub capture_group: Option<usize>,
+}
+
+/// A string literal checked by [`ApiKeyExtractor`]. See
+/// [`ApiKeyExtractor::with_string_observer`].
+///
+/// [`ApiKeyExtractor`]: crate::ApiKeyExtractor
+/// [`ApiKeyExtractor::with_string_observer`]: crate::ApiKeyExtractor::with_string_observer
+#[derive(Debug, Clone, Copy)]
+pub struct ScannedString<'a> {
+    /// The literal's value, without quotes
+    pub value: &'a str,
+    /// The variable or property name the literal is assigned to, if any.
+    pub identifier: Option<&'a str>,
+    /// Where the literal's value is in the source code.
+    pub span: Span,
+}
+
+/// The span of a string or template literal's contents, without its quotes.
+fn content_span(span: Span) -> Span {
+    debug_assert!(span.size() >= 2);
+    Span::new(span.start + 1, span.end - 1)
 }
 
 fn atom_as_source_str<'a>(atom: &Atom<'a>) -> &'a str {
@@ -52,20 +82,47 @@ fn atom_as_source_str<'a>(atom: &Atom<'a>) -> &'a str {
 
 pub(super) struct ApiKeyVisitor<'c, 'a> {
     config: &'c Config,
+    /// Holds strings built from constant expressions, e.g. `["a", "b"].join("")`
+    allocator: &'a Allocator,
+    /// The visited source code. Used to find where keys in literals with
+    /// escape sequences are.
+    source_text: &'a str,
+    observer: Option<&'c StringObserver>,
     api_keys: Vec<ApiKey<'a>>,
     // seen_api_key_name_rule_id: Option<&'c str>,
     current_identifier: Option<Atom<'a>>,
+    /// Path to the current property when inside server-rendered state, e.g.
+    /// `__NEXT_DATA__.props.pageProps`. Reported as the key name instead of
+    /// just the property name. See [`SSR_STATE_GLOBALS`].
+    state_path: Option<String>,
 }
 
 impl<'c, 'a> ApiKeyVisitor<'c, 'a> {
-    pub fn new(config: &'c Config) -> Self {
+    pub fn new(config: &'c Config, allocator: &'a Allocator) -> Self {
         Self {
             config,
+            allocator,
+            source_text: "",
+            observer: None,
             api_keys: vec![],
             current_identifier: None,
+            state_path: None,
         }
     }
 
+    /// The source code being visited, so keys are reported where they are
+    /// even in literals with escape sequences.
+    pub fn with_source_text(mut self, source_text: &'a str) -> Self {
+        self.source_text = source_text;
+        self
+    }
+
+    /// Call `observer` for every string literal checked.
+    pub fn with_observer(mut self, observer: Option<&'c StringObserver>) -> Self {
+        self.observer = observer;
+        self
+    }
+
     pub fn into_inner(self) -> Vec<ApiKey<'a>> {
         self.api_keys
     }
@@ -81,38 +138,105 @@ impl<'c, 'a> ApiKeyVisitor<'c, 'a> {
 
     fn find_and_report_api_keys(&mut self, maybe_secret: &Atom<'a>, span: Span) {
         let haystack = atom_as_source_str(maybe_secret);
-        let possible_found_secrets = self.config.check_values(haystack);
+        if let Some(observer) = self.observer {
+            observer.observe(&ScannedString {
+                value: haystack,
+                identifier: self.current_identifier.as_ref().map(atom_as_source_str),
+                span,
+            });
+        }
+        self.find_and_report_in(haystack, span, false);
+    }
+
+    /// Check a string built from a constant expression. Since the string does
+    /// not appear in the source code, found keys are reported at `span`, the
+    /// span of the whole expression.
+    fn find_and_report_api_keys_in_folded(&mut self, folded: &str, span: Span) {
+        let haystack: &'a str = self.allocator.alloc_str(folded);
+        self.find_and_report_in(haystack, span, true);
+    }
+
+    fn find_and_report_in(&mut self, haystack: &'a str, span: Span, folded: bool) {
+        let possible_found_secrets = self.config.check_values_with_groups(haystack);
 
         if let Some(identifier) = self.current_identifier.clone() {
             let violations = possible_found_secrets
-                .filter(|(rule_id, _, _)| self.config.check_name(*rule_id, &identifier));
-            self.record_with_span(span, Some(atom_as_source_str(&identifier)), violations);
+                .filter(|(rule_id, ..)| self.config.check_name(*rule_id, &identifier));
+            // name criteria only apply to the property name, but the whole
+            // path is more useful in reports
+            let key_name = match &self.state_path {
+                Some(path) => self.allocator.alloc_str(path),
+                None => atom_as_source_str(&identifier),
+            };
+            self.record_with_span(span, folded, haystack, Some(key_name), violations);
         } else {
             let violations = possible_found_secrets
-                .filter(|(rule_id, _, _)| self.config.get_name_criteria(*rule_id).is_none());
-            self.record_with_span(span, None, violations);
+                .filter(|(rule_id, ..)| self.config.get_name_criteria(*rule_id).is_none());
+            self.record_with_span(span, folded, haystack, None, violations);
         };
     }
 
     fn record_with_span(
         &mut self,
         span: Span,
+        folded: bool,
+        haystack: &'a str,
         identifier: Option<&'a str>,
-        violations: impl Iterator<Item = (RuleId, usize, &'a str)>,
+        violations: impl Iterator<Item = (RuleId, usize, &'a str, Option<usize>)>,
     ) {
-        violations.for_each(|(rule_id, key_start, found_key)| {
-            let start = span.start + key_start as u32;
-            let len = found_key.len() as u32;
-            let span = Span::new(start, start + len);
+        // the literal as written, including escape sequences
+        let raw = self
+            .source_text
+            .get(span.start as usize..span.end as usize)
+            .unwrap_or(haystack);
+        violations.for_each(|(rule_id, key_start, found_key, capture_group)| {
+            let span = if folded {
+                span
+            } else {
+                let key_end = key_start + found_key.len();
+                Span::new(
+                    span.start + raw_offset(raw, haystack, key_start) as u32,
+                    span.start + raw_offset(raw, haystack, key_end) as u32,
+                )
+            };
             self.api_keys.push(ApiKey {
                 rule_id,
                 span,
                 key_name: identifier,
                 secret: found_key,
+                capture_group,
             });
         });
     }
 
+    /// Walk server-rendered state assigned to `root`, tracking the path to
+    /// each property.
+    fn walk_state(&mut self, root: &str, state: &Expression<'a>)
+    where
+        'c: 'a,
+    {
+        let temp = self.state_path.replace(root.to_string());
+        walk::walk_expression(self, state);
+        self.state_path = temp;
+    }
+
+    /// Append `segment` to the state path, returning the path's previous
+    /// length for [`Self::pop_state_path`].
+    fn push_state_path(&mut self, segment: &str) -> usize {
+        let Some(path) = self.state_path.as_mut() else {
+            return 0;
+        };
+        let len = path.len();
+        path.push_str(segment);
+        len
+    }
+
+    fn pop_state_path(&mut self, len: usize) {
+        if let Some(path) = self.state_path.as_mut() {
+            path.truncate(len);
+        }
+    }
+
     // fn is_api_key(&self, maybe_key: &'a str) -> Option<&'c str> {
     //     if self.seen_api_key_name_rule_id.is_some() {
     //         self.seen_api_key_name_rule_id
@@ -149,16 +273,34 @@ where
         let temp = self.current_identifier.take();
 
         self.current_identifier = declarator.id.get_identifier_name();
-        walk::walk_expression(self, init);
+        match ssr_state_root(self.current_identifier.as_ref(), init) {
+            Some(root) => self.walk_state(root, init),
+            None => walk::walk_expression(self, init),
+        }
         self.current_identifier = temp;
     }
 
     fn visit_assignment_expression(&mut self, expr: &AssignmentExpression<'a>) {
         let temp = self.current_identifier.take();
-        walk::walk_expression(self, &expr.right);
+        // e.g. window.__NEXT_DATA__ = { props: { ... } }
+        match ssr_state_root(expr.left.get_identifier_name().as_ref(), &expr.right) {
+            Some(root) => self.walk_state(root, &expr.right),
+            None => walk::walk_expression(self, &expr.right),
+        }
         self.current_identifier = temp;
     }
 
+    fn visit_array_expression(&mut self, arr: &ArrayExpression<'a>) {
+        if self.state_path.is_none() {
+            return walk::walk_array_expression(self, arr);
+        }
+        for (i, element) in arr.elements.iter().enumerate() {
+            let len = self.push_state_path(&format!("[{i}]"));
+            walk::walk_array_expression_element(self, element);
+            self.pop_state_path(len);
+        }
+    }
+
     fn visit_property_definition(&mut self, def: &PropertyDefinition<'a>) {
         let Some(value) = def.value.as_ref() else {
             return;
@@ -170,14 +312,47 @@ where
         self.current_identifier = temp;
     }
 
+    fn visit_object_property(&mut self, prop: &ObjectProperty<'a>) {
+        let temp = self.current_identifier.take();
+
+        // keys can hold secrets too, e.g. `{ ["AKIA..."]: true }`
+        walk::walk_property_key(self, &prop.key);
+        self.current_identifier = prop.key.get_identifier_name();
+        let len = match &self.current_identifier {
+            Some(name) if self.state_path.is_some() => self.push_state_path(&format!(".{name}")),
+            _ => 0,
+        };
+        walk::walk_expression(self, &prop.value);
+        self.pop_state_path(len);
+        self.current_identifier = temp;
+    }
+
     fn visit_call_expression(&mut self, expr: &CallExpression<'a>) {
+        // e.g. ["AKIA", "IOSFODNN7EXAMPLE"].join(""). Checked before the
+        // identifier is cleared, since it applies to the joined string.
+        if let Some(joined) = expr.get_str_value() {
+            self.find_and_report_api_keys_in_folded(&joined, expr.span);
+        }
         let temp = self.current_identifier.take();
-        walk::walk_call_expression(self, expr);
+        if let Some(storage_key) = web_storage_set_item(expr) {
+            // localStorage.setItem("api_token", "...") is treated like an
+            // assignment to `api_token`
+            for (i, arg) in expr.arguments.iter().enumerate() {
+                self.current_identifier = if i == 1 {
+                    Some(storage_key.clone())
+                } else {
+                    None
+                };
+                walk::walk_argument(self, arg);
+            }
+        } else {
+            walk::walk_call_expression(self, expr);
+        }
         self.current_identifier = temp;
     }
 
     fn visit_string_literal(&mut self, lit: &StringLiteral<'a>) {
-        self.find_and_report_api_keys(&lit.value, lit.span);
+        self.find_and_report_api_keys(&lit.value, content_span(lit.span));
         // if let Some(rule_id) = self.is_api_key(&lit.value) {
         //     warn!(
         //         "Rule {} reported string literal '{}' as an API key",
@@ -187,10 +362,23 @@ where
         // }
     }
 
+    fn visit_tagged_template_expression(&mut self, expr: &TaggedTemplateExpression<'a>) {
+        // e.g. gql`query { viewer(token: "...") { id } }`. Each quasi is
+        // checked, even when there are substitutions. Tags get the raw
+        // strings, and cooked ones are missing when escapes are invalid.
+        self.visit_expression(&expr.tag);
+        for quasi in &expr.quasi.quasis {
+            self.find_and_report_api_keys(&quasi.value.raw, quasi.span);
+        }
+        for substitution in &expr.quasi.expressions {
+            self.visit_expression(substitution);
+        }
+    }
+
     fn visit_template_literal(&mut self, lit: &TemplateLiteral<'a>) {
         if lit.is_no_substitution_template() {
             let str_lit = lit.quasi().expect("TemplateLiteral.is_no_substitution_template() should have checked that at least one quasis exists.");
-            self.find_and_report_api_keys(&str_lit, lit.span)
+            self.find_and_report_api_keys(&str_lit, content_span(lit.span))
             // if let Some(rule_id) = self.is_api_key(str_lit) {
             //     warn!(
             //         "Rule {} reported template literal '{}' as an API key",
@@ -204,6 +392,68 @@ where
     }
 }
 
+/// Globals that server-rendering frameworks serialize page data into, e.g.
+/// Next.js's `window.__NEXT_DATA__` or Remix's `window.__remixContext`.
+/// Secrets leaked from server-side loaders end up in these.
+const SSR_STATE_GLOBALS: &[&str] = &[
+    "__NEXT_DATA__",
+    "__NUXT__",
+    "__remixContext",
+    "__staticRouterHydrationData",
+    "__APOLLO_STATE__",
+    "__INITIAL_STATE__",
+    "__PRELOADED_STATE__",
+];
+
+/// Returns the name of the global if `value` is an object or array literal
+/// assigned to server-rendered state. See [`SSR_STATE_GLOBALS`].
+fn ssr_state_root(name: Option<&Atom<'_>>, value: &Expression<'_>) -> Option<&'static str> {
+    let is_literal = matches!(
+        value.without_parentheses(),
+        Expression::ObjectExpression(_) | Expression::ArrayExpression(_)
+    );
+    if !is_literal {
+        return None;
+    }
+    let name = name?.as_str();
+    SSR_STATE_GLOBALS
+        .iter()
+        .find(|global| **global == name)
+        .copied()
+}
+
+/// Matches `localStorage.setItem(key, value)` and
+/// `sessionStorage.setItem(key, value)` calls with a constant `key`, returning
+/// the key. `window.` prefixes are allowed.
+fn web_storage_set_item<'a>(expr: &CallExpression<'a>) -> Option<Atom<'a>> {
+    let Expression::StaticMemberExpression(callee) = expr.callee.without_parentheses() else {
+        return None;
+    };
+    if callee.property.name != "setItem" {
+        return None;
+    }
+    let storage = match callee.object.without_parentheses() {
+        Expression::Identifier(ident) => &ident.name,
+        Expression::StaticMemberExpression(member) => match member.object.without_parentheses() {
+            Expression::Identifier(obj) if obj.name == "window" => &member.property.name,
+            _ => return None,
+        },
+        _ => return None,
+    };
+    if storage != "localStorage" && storage != "sessionStorage" {
+        return None;
+    }
+
+    let [key, _value, ..] = expr.arguments.as_slice() else {
+        return None;
+    };
+    match key {
+        Argument::StringLiteral(lit) => Some(lit.value.clone()),
+        Argument::TemplateLiteral(lit) if lit.is_no_substitution_template() => lit.quasi(),
+        _ => None,
+    }
+}
+
 impl fmt::Debug for ApiKeyVisitor<'_, '_> {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         f.debug_struct("ApiKeyVisitor")
@@ -14,7 +14,7 @@
 ///
 /// You should have received a copy of the GNU General Public License along with
 /// KeyHunter. If not, see <https://www.gnu.org/licenses/>.
-use std::borrow::Cow;
+use std::{borrow::Cow, iter::Peekable, str::CharIndices};
 
 use oxc::{
     ast::ast::*,
@@ -34,6 +34,7 @@ impl GetStrValue for Expression<'_> {
             Self::SequenceExpression(seq) => seq.get_str_value(),
             Self::BinaryExpression(expr) => expr.get_str_value(),
             Self::LogicalExpression(expr) => expr.get_str_value(),
+            Self::CallExpression(expr) => expr.get_str_value(),
             _ => None,
         }
     }
@@ -95,6 +96,134 @@ impl GetStrValue for LogicalExpression<'_> {
     }
 }
 
+/// Folds `["a", "b"].join("")` into `"ab"`. Only arrays of constant strings
+/// joined with an empty separator are folded.
+impl GetStrValue for CallExpression<'_> {
+    fn get_str_value(&self) -> Option<Cow<'_, str>> {
+        let Expression::StaticMemberExpression(callee) = self.callee.without_parentheses() else {
+            return None;
+        };
+        if callee.property.name != "join" {
+            return None;
+        }
+        let Expression::ArrayExpression(array) = callee.object.without_parentheses() else {
+            return None;
+        };
+        // `join()` uses "," as its separator
+        let [separator] = self.arguments.as_slice() else {
+            return None;
+        };
+        if !separator
+            .as_expression()
+            .and_then(GetStrValue::get_str_value)
+            .is_some_and(|separator| separator.is_empty())
+        {
+            return None;
+        }
+
+        array
+            .elements
+            .iter()
+            .try_fold(String::new(), |mut joined, element| {
+                joined.push_str(&element.as_expression()?.get_str_value()?);
+                Some(joined)
+            })
+            .map(Cow::Owned)
+    }
+}
+
+/// Map a byte offset into a string or template literal's value to the same
+/// position in `raw`, the literal's source text without its quotes. Escape
+/// sequences like `\x41` and `\u{1F511}` take up more room in source code
+/// than in the value they produce.
+pub(super) fn raw_offset(raw: &str, value: &str, offset: usize) -> usize {
+    // no escapes
+    if raw.len() == value.len() {
+        return offset;
+    }
+
+    let mut cooked = 0;
+    let mut chars = raw.char_indices().peekable();
+    while let Some((i, c)) = chars.next() {
+        let len = match c {
+            '\\' => escape_len(&mut chars),
+            // template literals normalize line endings
+            '\r' => {
+                chars.next_if(|&(_, c)| c == '\n');
+                1
+            }
+            c => c.len_utf8(),
+        };
+        // line continuations produce nothing, so skip past them
+        if cooked >= offset && len > 0 {
+            return i;
+        }
+        cooked += len;
+    }
+    raw.len()
+}
+
+/// Length in bytes of the character an escape sequence produces, with `chars`
+/// just past the backslash.
+fn escape_len(chars: &mut Peekable<CharIndices<'_>>) -> usize {
+    let Some((_, c)) = chars.next() else {
+        return 0;
+    };
+    match c {
+        // line continuation
+        '\n' | '\u{2028}' | '\u{2029}' => 0,
+        '\r' => {
+            chars.next_if(|&(_, c)| c == '\n');
+            0
+        }
+        'x' => code_point_len(take_hex(chars, 2)),
+        'u' => {
+            let code = if chars.next_if(|&(_, c)| c == '{').is_some() {
+                let code = take_hex(chars, 6);
+                chars.next_if(|&(_, c)| c == '}');
+                code
+            } else {
+                take_hex(chars, 4)
+            };
+            match code {
+                // the high half of a surrogate pair produces the whole
+                // character, and the low half produces nothing
+                0xD800..=0xDBFF => 4,
+                0xDC00..=0xDFFF => 0,
+                code => code_point_len(code),
+            }
+        }
+        // legacy octal escapes, e.g. `\101`, are at most `\377`
+        '0'..='7' => {
+            let max_digits = if c <= '3' { 2 } else { 1 };
+            let mut code = c.to_digit(8).unwrap();
+            for _ in 0..max_digits {
+                match chars.next_if(|&(_, c)| c.is_digit(8)) {
+                    Some((_, c)) => code = code * 8 + c.to_digit(8).unwrap(),
+                    None => break,
+                }
+            }
+            code_point_len(code)
+        }
+        c => c.len_utf8(),
+    }
+}
+
+fn take_hex(chars: &mut Peekable<CharIndices<'_>>, max_digits: usize) -> u32 {
+    let mut code = 0;
+    for _ in 0..max_digits {
+        match chars.next_if(|&(_, c)| c.is_ascii_hexdigit()) {
+            Some((_, c)) => code = code * 16 + c.to_digit(16).unwrap(),
+            None => break,
+        }
+    }
+    code
+}
+
+fn code_point_len(code: u32) -> usize {
+    char::from_u32(code).map_or(char::REPLACEMENT_CHARACTER.len_utf8(), char::len_utf8)
+}
+
 #[cfg(test)]
 mod test {
     use super::*;
@@ -182,6 +311,12 @@ mod test {
             r#"
                 "Hello" + ", " + ("World!" || false)
             "#,
+            r#"
+                ["Hello", ", ", `World`, "!"].join("")
+            "#,
+            r#"
+                ["Hello, ", "Wor" + "ld!"].join(``)
+            "#,
         ];
 
         for test in test_cases {
@@ -196,4 +331,51 @@ mod test {
             );
         }
     }
+
+    #[test]
+    fn test_array_join_not_folded() {
+        let allocator = Allocator::default();
+        let test_cases = vec![
+            r#"["Hello", "World!"].join(" ")"#,
+            r#"["Hello", "World!"].join()"#,
+            r#"["Hello", "World!"].join(sep)"#,
+            r#"["Hello", x].join("")"#,
+            r#"["Hello", ...rest].join("")"#,
+            r#"["Hello", , "World!"].join("")"#,
+            r#"arr.join("")"#,
+            r#"["Hello", "World!"].concat("")"#,
+        ];
+
+        for test in test_cases {
+            let program = parse(&allocator, test);
+            let Statement::ExpressionStatement(stmt) = &program.body[0] else {
+                panic!("Expected program to contain an expression statement: {test}");
+            };
+
+            assert_eq!(stmt.expression.get_str_value(), None, "{test}");
+        }
+    }
+
+    #[test]
+    fn test_raw_offset() {
+        // (raw, value, value offset, raw offset)
+        let test_cases = [
+            ("abc", "abc", 2, 2),
+            (r"\x41KIA", "AKIA", 0, 0),
+            (r"\x41KIA", "AKIA", 1, 4),
+            (r"\x41KIA", "AKIA", 4, 7),
+            (r"a\nb", "a\nb", 2, 3),
+            (r"\u0020key", " key", 1, 6),
+            (r"\u{1F511}key", "\u{1F511}key", 4, 9),
+            (r"\uD83D\uDD11key", "\u{1F511}key", 4, 12),
+            (r"\101\0b", "A\0b", 2, 6),
+            ("\\éa", "éa", 2, 3),
+            ("a\\\nb", "ab", 1, 3),
+            ("a\r\nb", "a\nb", 2, 3),
+        ];
+
+        for (raw, value, offset, expected) in test_cases {
+            assert_eq!(raw_offset(raw, value, offset), expected, "{raw}");
+        }
+    }
 }
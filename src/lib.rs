@@ -18,13 +18,88 @@
 //  KeyHunter. If not, see <https://www.gnu.org/licenses/>.
 mod config;
 mod extract;
-pub(crate) mod http;
+mod http;
+pub mod pipeline;
 #[cfg(feature = "report")]
 pub mod report;
+pub mod span;
+#[cfg(test)]
+pub(crate) mod testing;
+// lets `testing` name this crate the same way integration tests do
+#[cfg(test)]
+extern crate self as keyhunter;
+#[cfg(feature = "verify")]
+pub mod verify;
 mod walk;
 
-pub use config::{Config, RuleId};
+pub use config::{
+    validators, AuthHeaders, Config, ConfigStats, CustomRule, IgnoreFile, Pattern, RegexLimits,
+    RejectedRule, RuleId, SecretNames, Validator,
+};
 pub use extract::{
     ApiKeyCollector, ApiKeyError, ApiKeyExtractor, ApiKeyMessage, ApiKeyReceiver, ApiKeySender,
+    ContentType, DownloadScriptDiagnostic, Downloader, ExtractionTimeoutDiagnostic, FailureKind,
+    Finding, ScannedString, ScriptKind, UreqDownloader,
+};
+pub use http::UaProfile;
+pub use walk::{
+    CacheStats, CancellationToken, PageOffset, Script, ScriptMessage, ScriptReceiver,
+    WebsiteWalkBuilder, WebsiteWalker,
 };
-pub use walk::{ScriptMessage, ScriptReceiver, WebsiteWalkBuilder, WebsiteWalker};
+
+/// The version of `keyhunter` being used, e.g. `0.2.0`.
+pub const VERSION: &str = env!("CARGO_PKG_VERSION");
+
+/// The version of `keyhunter` being used, e.g. `0.2.0`. Reports use this to
+/// record what produced them.
+pub const fn version() -> &'static str {
+    VERSION
+}
+
+/// The name reports use for this tool, i.e. `keyhunter`.
+pub const fn tool_name() -> &'static str {
+    env!("CARGO_PKG_NAME")
+}
+
+#[cfg(test)]
+mod test {
+    use std::fs;
+
+    use crate::testing::{Response, TestServer};
+
+    /// Lives here rather than in `testing`, which integration tests include
+    /// too, so that it only runs once.
+    #[test]
+    fn test_test_server_dir() {
+        let root = std::env::temp_dir().join(format!("keyhunter-site-{}", rand::random::<u32>()));
+        fs::create_dir_all(root.join("js")).unwrap();
+        fs::write(root.join("index.html"), "<html></html>").unwrap();
+        fs::write(root.join("js/app.js"), "console.log(1)").unwrap();
+
+        let server = TestServer::builder()
+            .dir(&root)
+            .route("/api", Response::js("{}"))
+            .start();
+        let get = |path: &str| ureq::get(&server.url_for(path)).call();
+
+        let index = get("/").unwrap();
+        assert_eq!(index.content_type(), "text/html");
+        assert_eq!(index.into_string().unwrap(), "<html></html>");
+        let script = get("/js/app.js?v=2").unwrap();
+        assert_eq!(script.content_type(), "application/javascript");
+        assert_eq!(script.into_string().unwrap(), "console.log(1)");
+        // routes take precedence
+        assert_eq!(get("/api").unwrap().into_string().unwrap(), "{}");
+        assert!(matches!(
+            get("/missing.js"),
+            Err(ureq::Error::Status(404, _))
+        ));
+        assert!(matches!(
+            get("/../etc/passwd"),
+            Err(ureq::Error::Status(404, _))
+        ));
+
+        drop(server);
+        fs::remove_dir_all(&root).unwrap();
+    }
+}
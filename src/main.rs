@@ -19,22 +19,28 @@ extern crate pretty_env_logger;
 extern crate log;
 
 mod cmd;
+#[cfg(test)]
+#[path = "testing.rs"]
+mod testing;
 
 use miette::{GraphicalTheme, IntoDiagnostic, Result};
 use owo_colors::OwoColorize;
-use std::{process::ExitCode, sync::Arc, thread};
+use std::{collections::BTreeMap, process::ExitCode, sync::Arc, thread};
 
 use clap::Parser;
 use cmd::{
+    checkpoint::Checkpoint,
     cli::{Cli, OutputFormat},
     runner::Runner,
+    signal,
 };
 use keyhunter::{
     report::{
-        GraphicalReportHandler as KeyhunterGraphicalReportHandler, JsonReportHandler,
+        Baseline, GraphicalReportHandler as KeyhunterGraphicalReportHandler, JsonReportHandler,
         ReportHandler, Reporter,
     },
-    ApiKeyMessage, Config,
+    ApiKeyMessage, AuthHeaders, CancellationToken, Config, FailureKind, Finding, IgnoreFile,
+    Script, SecretNames,
 };
 
 fn main() -> Result<ExitCode> {
@@ -66,18 +72,60 @@ fn main() -> Result<ExitCode> {
 
     debug!("cli args: {:?}", cmd);
 
-    let config = Config::gitleaks();
+    let config = match cmd.config() {
+        Some(path) => Config::from_gitleaks_file(path)?,
+        None => Config::gitleaks(),
+    };
+    let config = if cmd.rules().is_empty() {
+        config
+    } else {
+        config.with_rules_matching(cmd.rules())
+    };
+    let config = config.without_rules_matching(cmd.exclude_rules());
+    let config = match cmd.secret_names() {
+        Some([]) => config.with_secret_names(SecretNames::default()),
+        Some(keywords) => config.with_secret_names(SecretNames::new(keywords)),
+        None => config,
+    };
+    let config = match cmd.auth_headers() {
+        Some([]) => config.with_auth_headers(AuthHeaders::default()),
+        Some(headers) => config.with_auth_headers(AuthHeaders::new(headers)),
+        None => config,
+    };
+
+    let ignore = match cmd.ignore_file() {
+        Some(path) => Some(IgnoreFile::load(path)?),
+        None => IgnoreFile::find(".")?,
+    };
+
+    if cmd.insecure() {
+        eprintln!(
+            "{}",
+            "Warning: TLS certificate verification is disabled. Only scan sites you control with --insecure."
+                .yellow()
+        );
+    }
 
     let start = std::time::Instant::now();
 
     let reporter = {
         let handler: Box<dyn ReportHandler + Send + Sync> = match cmd.format() {
             OutputFormat::Default => Box::new(
-                KeyhunterGraphicalReportHandler::default().with_redacted(cmd.is_redacted()),
+                KeyhunterGraphicalReportHandler::default()
+                    .with_redacted(cmd.is_redacted())
+                    .with_verbose(cmd.is_verbose()),
             ),
             OutputFormat::Json => Box::new(JsonReportHandler::default()),
         };
-        Reporter::new(handler)
+        let reporter = Reporter::new(handler).with_sorted(cmd.sort());
+        let reporter = match ignore.clone() {
+            Some(ignore) => reporter.with_ignore(ignore),
+            None => reporter,
+        };
+        match cmd.since() {
+            Some(path) => reporter.with_baseline(Baseline::load(path)?),
+            None => reporter,
+        }
     };
     // let reporter: Reporter<_> = ReporterBuilder::default()
     //     .with_redacted(cmd.is_redacted())
@@ -88,11 +136,42 @@ fn main() -> Result<ExitCode> {
         cmd.max_args(),
         cmd.headers().into(),
         cmd.random_ua(),
-    );
-    let (key_receiver, handle) = runner.run(vec![cmd.entrypoint().clone()]);
+    )
+    .with_scan_json(cmd.scan_json())
+    .with_config_probing(cmd.probe_paths())
+    .with_endpoints(cmd.endpoints())
+    .with_tls_verification(!cmd.insecure())
+    .with_extraction_timeout(cmd.extract_timeout())
+    .with_locales(cmd.locales().to_vec())
+    .with_max_keys(cmd.max_keys())
+    .with_max_scripts(cmd.max_scripts())
+    .with_concurrency(cmd.concurrency());
+    // stop crawling on Ctrl-C, but still report keys that were already found
+    let cancel = CancellationToken::new();
+    signal::cancel_on_interrupt(cancel.clone());
+    let runner = runner.with_cancel(cancel);
+    let runner = match ignore {
+        Some(ignore) => runner.with_ignore(ignore),
+        None => runner,
+    };
+    let runner = match cmd.checkpoint() {
+        Some(path) => runner.with_checkpoint(Checkpoint::open(path, cmd.resume())?),
+        None => runner,
+    };
+
+    if cmd.list_scripts() {
+        let mut scripts = vec![];
+        for url in cmd.entrypoints() {
+            scripts.extend(runner.list_scripts(url.clone())?);
+        }
+        print_scripts(scripts);
+        return Ok(ExitCode::SUCCESS);
+    }
+    let (key_receiver, handle) = runner.run(cmd.entrypoints().to_vec());
 
     // Render reports for scraped credentials
     let moved_reporter = Arc::clone(&reporter);
+    let format = cmd.format();
     let recv_handle = thread::spawn(move || {
         let reporter = moved_reporter;
         while let Ok(message) = key_receiver.recv() {
@@ -101,15 +180,28 @@ fn main() -> Result<ExitCode> {
                 ApiKeyMessage::Keys(api_keys) => {
                     (*reporter).report_keys(&api_keys).unwrap();
                 }
-                ApiKeyMessage::RecoverableFailure(err) => {
-                    println!("{:?}", err);
-                }
+                ApiKeyMessage::RecoverableFailure { kind, error } => match kind {
+                    // unreachable scripts are common and not worth
+                    // interrupting the report for
+                    FailureKind::Download | FailureKind::Decode => warn!("{error:?}"),
+                    _ => println!("{:?}", error),
+                },
                 ApiKeyMessage::DidScanScript => {
                     reporter.record_scripts_checked(1);
                 }
-                ApiKeyMessage::DidScrapePages(pages) => {
-                    reporter.record_pages_crawled(pages);
+                ApiKeyMessage::DidScrapePage { .. } => {
+                    reporter.record_pages_crawled(1);
                 }
+                ApiKeyMessage::PageError { .. } => {
+                    // already logged by the walker
+                    reporter.record_page_errors(1);
+                }
+                ApiKeyMessage::Endpoints {
+                    script_url,
+                    endpoints,
+                } => print_endpoints(format, &script_url, &endpoints),
+                // not enabled by the CLI
+                ApiKeyMessage::ScriptSource { .. } => {}
             }
             // println!("{:?}", api_key);
         }
@@ -117,15 +209,25 @@ fn main() -> Result<ExitCode> {
 
     let errors = handle.join().unwrap();
     recv_handle.join().unwrap();
+    for error in &errors {
+        println!("{error:?}");
+    }
+    reporter.flush()?;
     let end = std::time::Instant::now();
     let elapsed = (end - start).as_secs_f64();
 
     let num_scripts = reporter.scripts_checked();
     let num_keys = reporter.keys_found();
     let num_pages = reporter.pages_crawled();
+    let num_page_errors = reporter.page_errors();
+    let resolved: Vec<Finding> = reporter.resolved().into_iter().cloned().collect();
+    let summary = reporter.summary();
     drop(reporter);
 
     if cmd.format().is_default() {
+        if cmd.summary() && !summary.is_empty() {
+            print!("{summary}");
+        }
         println!(
             "Found {} {} across {} {} and {} {} in {:.2}{}",
             num_keys.yellow(),
@@ -141,10 +243,113 @@ fn main() -> Result<ExitCode> {
             elapsed.cyan(),
             "s".cyan()
         );
+        if let Some(stats) = runner.cache_stats() {
+            if stats.url_hits + stats.script_hits > 0 {
+                println!(
+                    "Skipped {} already visited {} and {} duplicate {} ({:.0}% and {:.0}% cache hit rates)",
+                    stats.url_hits.cyan(),
+                    if stats.url_hits == 1 { "page" } else { "pages" },
+                    stats.script_hits.cyan(),
+                    if stats.script_hits == 1 {
+                        "script"
+                    } else {
+                        "scripts"
+                    },
+                    stats.url_hit_rate() * 100.0,
+                    stats.script_hit_rate() * 100.0,
+                );
+            }
+        }
+        if cmd.since().is_some() {
+            println!(
+                "{} previously reported {} resolved",
+                resolved.len().green(),
+                if resolved.len() == 1 {
+                    "key was"
+                } else {
+                    "keys were"
+                },
+            );
+            for finding in &resolved {
+                println!("  {} in {}", finding.rule_id, finding.url);
+            }
+        }
+        if num_page_errors > 0 {
+            println!(
+                "{} {} could not be crawled",
+                num_page_errors.red(),
+                if num_page_errors == 1 {
+                    "page"
+                } else {
+                    "pages"
+                },
+            );
+        }
     }
-    if errors.is_empty() {
+    if signal::interrupted() {
+        eprintln!("Scan was interrupted, results are incomplete");
+        Ok(ExitCode::from(signal::EXIT_INTERRUPTED))
+    } else if errors.is_empty() {
         Ok(ExitCode::SUCCESS)
     } else {
         Ok(ExitCode::FAILURE)
     }
 }
+
+/// Print URLs and API paths found in a script, one per line.
+fn print_endpoints(format: OutputFormat, script_url: &url::Url, endpoints: &[String]) {
+    for endpoint in endpoints {
+        if format.is_json() {
+            println!(
+                "{}",
+                serde_json::json!({ "endpoint": endpoint, "script_url": script_url.as_str() })
+            );
+        } else {
+            println!("{} {endpoint} ({script_url})", "endpoint:".cyan());
+        }
+    }
+}
+
+/// Print deduplicated script URLs, followed by the number of embedded scripts
+/// found on each page.
+fn print_scripts(scripts: Vec<Script>) {
+    let mut urls: Vec<String> = vec![];
+    let mut embedded: BTreeMap<String, usize> = BTreeMap::new();
+    for script in scripts {
+        match script {
+            Script::Url(url) => urls.push(url.to_string()),
+            Script::Embedded(_, page_url, _) => {
+                *embedded.entry(page_url.to_string()).or_default() += 1
+            }
+        }
+    }
+    urls.sort_unstable();
+    urls.dedup();
+
+    for url in &urls {
+        println!("{url}");
+    }
+    for (page_url, count) in &embedded {
+        println!(
+            "{} embedded {} in {}",
+            count.yellow(),
+            if *count == 1 { "script" } else { "scripts" },
+            page_url
+        );
+    }
+    println!(
+        "Found {} {} and {} embedded {}",
+        urls.len().yellow(),
+        if urls.len() == 1 {
+            "script URL"
+        } else {
+            "script URLs"
+        },
+        embedded.values().sum::<usize>().yellow(),
+        if embedded.values().sum::<usize>() == 1 {
+            "script"
+        } else {
+            "scripts"
+        },
+    );
+}
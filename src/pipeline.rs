@@ -0,0 +1,687 @@
+//  Copyright © 2024 Don Isaac
+//
+//  This file is part of KeyHunter.
+//
+//  KeyHunter is free software: you can redistribute it and/or modify it
+//  under the terms of the GNU General Public License as published by the Free
+//  Software Foundation, either version 3 of the License, or (at your option)
+//  any later version.
+//
+//  KeyHunter is distributed in the hope that it will be useful, but WITHOUT
+//  ANY WARRANTY; without even the implied warranty of MERCHANTABILITY or
+//  FITNESS FOR A PARTICULAR PURPOSE. See the GNU General Public License for
+//  more details.
+//
+//  You should have received a copy of the GNU General Public License along with
+//  KeyHunter. If not, see <https://www.gnu.org/licenses/>.
+//! Scan many sites for API keys.
+//!
+//! A [`Pipeline`] crawls sites with [`WebsiteWalker`]s, checks the scripts
+//! they find with [`ApiKeyCollector`]s, and sends everything found over a
+//! single channel, so walkers, collectors, and their channels do not need
+//! to be wired up by hand.
+//!
+//! ```no_run
+//! use keyhunter::{pipeline::Pipeline, ApiKeyMessage};
+//!
+//! let pipeline = Pipeline::builder().with_max_walks(20).build();
+//! let (receiver, handle) = pipeline.run(vec!["https://example.com".to_string()]);
+//! for message in receiver {
+//!     if let ApiKeyMessage::Keys(keys) = message {
+//!         println!("found {} keys", keys.len());
+//!     }
+//! }
+//! let errors = handle.join().unwrap();
+//! ```
+//!
+//! Use [`Pipeline::scan`] to wait for the scan to finish and get everything
+//! it found as a single [`ScanReport`].
+use log::{debug, trace, warn};
+use std::{
+    fmt,
+    num::NonZeroUsize,
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        mpsc, Arc, Mutex,
+    },
+    thread::{self, JoinHandle},
+    time::Duration,
+};
+
+use miette::{Context as _, Error, IntoDiagnostic as _, Result};
+use serde::Serialize;
+use ureq::Agent;
+
+#[cfg(feature = "report")]
+use crate::report::{ReportHandler, Reporter};
+use crate::{
+    ApiKeyCollector, ApiKeyError, ApiKeyMessage, ApiKeyReceiver, ApiKeySender, CacheStats,
+    CancellationToken, Config, FailureKind, IgnoreFile, Script, WebsiteWalkBuilder, WebsiteWalker,
+};
+
+/// Remembers which sites a [`Pipeline`] has finished scanning, so that an
+/// interrupted run can be resumed without rescanning them.
+pub trait CompletedSites: Send + Sync {
+    /// Was `url` completely scanned by a previous run?
+    fn is_completed(&self, url: &str) -> bool;
+    /// Record that `url` was completely scanned.
+    fn mark_completed(&self, url: &str) -> Result<()>;
+}
+
+/// Everything found by [`Pipeline::scan`].
+#[derive(Debug, Default, Serialize)]
+#[non_exhaustive]
+pub struct ScanReport {
+    /// Keys found on every site, in the order they were found
+    pub findings: Vec<ApiKeyError>,
+    pub stats: ScanStats,
+    /// Why each script that could not be checked was skipped
+    pub errors: Vec<FailureKind>,
+}
+
+/// Counts recorded while building a [`ScanReport`].
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Serialize)]
+#[non_exhaustive]
+pub struct ScanStats {
+    pub pages_crawled: usize,
+    /// Pages that could not be fetched
+    pub page_errors: usize,
+    pub scripts_checked: usize,
+    /// Number of [findings](ScanReport::findings), including duplicates
+    pub keys_found: usize,
+    /// Sites that could not be scanned at all, e.g. because their entrypoint
+    /// could not be reached
+    pub sites_failed: usize,
+}
+
+/// Crawls sites, checks the scripts found on them for API keys, and sends
+/// the results over a single channel.
+///
+/// Create one with [`Pipeline::builder`].
+#[derive(Debug, Clone)]
+pub struct Pipeline {
+    options: PipelineBuilder,
+    /// Walk cache stats from the last run
+    cache_stats: Arc<Mutex<Option<CacheStats>>>,
+}
+
+/// Configures a [`Pipeline`].
+#[derive(Clone)]
+#[must_use]
+pub struct PipelineBuilder {
+    config: Arc<Config>,
+    max_walks: Option<usize>,
+    headers: Vec<(String, String)>,
+    random_ua: bool,
+    scan_json: bool,
+    /// Well-known config file paths checked on each site
+    config_paths: Vec<String>,
+    endpoints: bool,
+    /// Maximum number of sites scanned at once
+    concurrency: NonZeroUsize,
+    /// Abandon scripts that take longer than this to check
+    extraction_timeout: Option<Duration>,
+    /// `Accept-Language` values each site is crawled with
+    locales: Vec<String>,
+    /// Stop scanning a site once this many keys have been found on it
+    max_keys: Option<usize>,
+    /// Stop scanning once this many scripts have been checked across all
+    /// sites
+    max_scripts: Option<usize>,
+    /// Stops every scan, e.g. when the user presses Ctrl-C
+    cancel: Option<CancellationToken>,
+    /// Scripts matching this ignore file are not checked
+    ignore: Option<Arc<IgnoreFile>>,
+    /// Records completed sites and skips ones completed by a previous run
+    completed: Option<Arc<dyn CompletedSites>>,
+    timeout: Option<Duration>,
+    timeout_connect: Option<Duration>,
+    /// When `false`, invalid TLS certificates are accepted
+    verify_tls: bool,
+    /// Used for every request instead of agents built by walkers and
+    /// collectors
+    agent: Option<Agent>,
+}
+
+impl fmt::Debug for PipelineBuilder {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("PipelineBuilder")
+            .field("max_walks", &self.max_walks)
+            .field("headers", &self.headers)
+            .field("random_ua", &self.random_ua)
+            .field("scan_json", &self.scan_json)
+            .field("config_paths", &self.config_paths)
+            .field("endpoints", &self.endpoints)
+            .field("concurrency", &self.concurrency)
+            .field("extraction_timeout", &self.extraction_timeout)
+            .field("locales", &self.locales)
+            .field("max_keys", &self.max_keys)
+            .field("max_scripts", &self.max_scripts)
+            .field("cancel", &self.cancel)
+            .field("ignore", &self.ignore)
+            .field("completed", &self.completed.is_some())
+            .field("timeout", &self.timeout)
+            .field("timeout_connect", &self.timeout_connect)
+            .field("verify_tls", &self.verify_tls)
+            .field("agent", &self.agent)
+            .finish_non_exhaustive()
+    }
+}
+
+impl Default for PipelineBuilder {
+    fn default() -> Self {
+        Self {
+            config: Default::default(),
+            max_walks: None,
+            headers: vec![],
+            random_ua: false,
+            scan_json: false,
+            config_paths: vec![],
+            endpoints: false,
+            concurrency: NonZeroUsize::MIN,
+            extraction_timeout: None,
+            locales: vec![],
+            max_keys: None,
+            max_scripts: None,
+            cancel: None,
+            ignore: None,
+            completed: None,
+            timeout: None,
+            timeout_connect: None,
+            verify_tls: true,
+            agent: None,
+        }
+    }
+}
+
+impl PipelineBuilder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Rules used to find API keys.
+    ///
+    /// Default [`Config::default`]
+    pub fn with_config(mut self, config: Arc<Config>) -> Self {
+        self.config = config;
+        self
+    }
+
+    /// Visit at most `max_walks` pages on each site.
+    ///
+    /// See [`WebsiteWalkBuilder::with_max_walks`].
+    pub fn with_max_walks(mut self, max_walks: usize) -> Self {
+        self.max_walks = Some(max_walks);
+        self
+    }
+
+    /// Include these headers in all requests made while crawling sites and
+    /// downloading scripts.
+    pub fn with_headers<I>(mut self, headers: I) -> Self
+    where
+        I: IntoIterator<Item = (String, String)>,
+    {
+        self.headers.extend(headers);
+        self
+    }
+
+    /// Set the `User-Agent` header to a random, browser-like value.
+    ///
+    /// See [`WebsiteWalkBuilder::with_random_ua`].
+    pub fn with_random_ua(mut self, yes: bool) -> Self {
+        self.random_ua = yes;
+        self
+    }
+
+    /// Also check JSON files referenced by scripts for API keys.
+    ///
+    /// See [`ApiKeyCollector::with_json_scanning`].
+    pub fn with_scan_json(mut self, yes: bool) -> Self {
+        self.scan_json = yes;
+        self
+    }
+
+    /// Also check these config files (e.g. `/.env`) on each site. An empty
+    /// list disables probing.
+    ///
+    /// See [`ApiKeyCollector::with_config_probing`].
+    pub fn with_config_probing(mut self, paths: Vec<String>) -> Self {
+        self.config_paths = paths;
+        self
+    }
+
+    /// Also report URLs and API paths referenced by scripts.
+    ///
+    /// See [`ApiKeyCollector::with_endpoint_discovery`].
+    pub fn with_endpoints(mut self, yes: bool) -> Self {
+        self.endpoints = yes;
+        self
+    }
+
+    /// Give up checking a script for API keys after `timeout`. [`None`]
+    /// disables the timeout.
+    ///
+    /// See [`ApiKeyCollector::with_extraction_timeout`].
+    pub fn with_extraction_timeout(mut self, timeout: Option<Duration>) -> Self {
+        self.extraction_timeout = timeout;
+        self
+    }
+
+    /// Crawl each site once per locale, sending it as the `Accept-Language`
+    /// header.
+    ///
+    /// See [`WebsiteWalkBuilder::with_locales`].
+    pub fn with_locales(mut self, locales: Vec<String>) -> Self {
+        self.locales = locales;
+        self
+    }
+
+    /// Stop crawling a site once `max_keys` keys have been found on it.
+    /// [`None`] scans sites completely.
+    ///
+    /// See [`ApiKeyCollector::with_max_keys`].
+    pub fn with_max_keys(mut self, max_keys: Option<usize>) -> Self {
+        self.max_keys = max_keys;
+        self
+    }
+
+    /// Stop scanning once `max_scripts` scripts have been checked for keys,
+    /// counted across every site in the run. Sites still being crawled stop
+    /// early and remaining sites are skipped. [`None`] checks every script.
+    ///
+    /// See [`ApiKeyCollector::with_max_scripts`].
+    pub fn with_max_scripts(mut self, max_scripts: Option<usize>) -> Self {
+        self.max_scripts = max_scripts;
+        self
+    }
+
+    /// Scan up to `concurrency` sites at once.
+    ///
+    /// Default `1`
+    ///
+    /// # Panics
+    /// if `concurrency` is zero.
+    pub fn with_concurrency(mut self, concurrency: usize) -> Self {
+        self.concurrency =
+            NonZeroUsize::new(concurrency).expect("concurrency must be greater than zero");
+        self
+    }
+
+    /// Stop scanning once `cancel` is cancelled.
+    ///
+    /// Sites being crawled stop visiting new pages, but scripts already found
+    /// are still checked, so their keys are reported. Sites that have not
+    /// been started are skipped, and interrupted sites are not marked as
+    /// completed.
+    pub fn with_cancel(mut self, cancel: CancellationToken) -> Self {
+        self.cancel = Some(cancel);
+        self
+    }
+
+    /// Skip scripts matching `ignore`.
+    ///
+    /// See [`ApiKeyCollector::with_ignore`].
+    pub fn with_ignore(mut self, ignore: IgnoreFile) -> Self {
+        self.ignore = Some(Arc::new(ignore));
+        self
+    }
+
+    /// Record each site in `completed` once it has been scanned, skipping
+    /// sites that were completed by a previous run.
+    pub fn with_completed_sites<C: CompletedSites + 'static>(mut self, completed: C) -> Self {
+        self.completed = Some(Arc::new(completed));
+        self
+    }
+
+    /// See [`WebsiteWalkBuilder::with_timeout`].
+    pub fn with_timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = Some(timeout);
+        self
+    }
+
+    /// See [`WebsiteWalkBuilder::with_timeout_connect`].
+    pub fn with_timeout_connect(mut self, timeout: Duration) -> Self {
+        self.timeout_connect = Some(timeout);
+        self
+    }
+
+    /// Check that sites' TLS certificates are valid. Disable verification to
+    /// scan staging sites with self-signed certificates.
+    ///
+    /// See [`WebsiteWalkBuilder::with_tls_verification`].
+    ///
+    /// Default `true`
+    pub fn with_tls_verification(mut self, yes: bool) -> Self {
+        self.verify_tls = yes;
+        self
+    }
+
+    /// Crawl sites and download scripts with a preconfigured [`Agent`], e.g.
+    /// one that sends requests through a proxy.
+    ///
+    /// Timeouts and TLS settings on this builder are ignored; configure them
+    /// on the agent instead. See [`WebsiteWalkBuilder::with_agent`] and
+    /// [`ApiKeyCollector::with_agent`].
+    pub fn with_agent(mut self, agent: Agent) -> Self {
+        self.agent = Some(agent);
+        self
+    }
+
+    pub fn build(self) -> Pipeline {
+        Pipeline {
+            options: self,
+            cache_stats: Default::default(),
+        }
+    }
+}
+
+impl Pipeline {
+    pub fn builder() -> PipelineBuilder {
+        PipelineBuilder::default()
+    }
+
+    /// How often the cache shared by all sites prevented pages from being
+    /// revisited and scripts from being checked again. Available once
+    /// [`Pipeline::run`] has finished.
+    pub fn cache_stats(&self) -> Option<CacheStats> {
+        *self.cache_stats.lock().unwrap()
+    }
+
+    fn is_cancelled(&self) -> bool {
+        self.options
+            .cancel
+            .as_ref()
+            .is_some_and(CancellationToken::is_cancelled)
+    }
+
+    /// Scan each site in `urls` for API keys.
+    ///
+    /// Sites are scanned concurrently (see
+    /// [`PipelineBuilder::with_concurrency`]), and keys from all of them are
+    /// sent over the returned channel. An [`ApiKeyMessage::Stop`] is sent
+    /// once every site has been scanned. The returned thread yields errors
+    /// for sites that could not be scanned.
+    pub fn run<U>(&self, urls: U) -> (ApiKeyReceiver, JoinHandle<Vec<Error>>)
+    where
+        U: IntoIterator<Item = String> + Send + 'static,
+        U::IntoIter: Send,
+    {
+        let (key_sender, key_receiver) = mpsc::channel::<ApiKeyMessage>();
+        let pipeline = self.clone();
+
+        trace!("Starting pipeline thread");
+        let handle = thread::spawn(move || {
+            let walk_builder = pipeline.walk_builder();
+            let urls = Mutex::new(urls.into_iter());
+            let errors: Mutex<Vec<Error>> = Default::default();
+            // shared by every site's collector so `max_scripts` applies to
+            // the whole run
+            let scripts_checked: Arc<AtomicUsize> = Default::default();
+
+            thread::scope(|scope| {
+                for _ in 0..pipeline.options.concurrency.get() {
+                    scope.spawn(|| loop {
+                        if pipeline.is_cancelled() {
+                            debug!("Scan cancelled, skipping remaining sites");
+                            break;
+                        }
+                        if pipeline.options.max_scripts.is_some_and(|max_scripts| {
+                            scripts_checked.load(Ordering::Relaxed) >= max_scripts
+                        }) {
+                            debug!("Checked max scripts, skipping remaining sites");
+                            break;
+                        }
+                        // the queue is unlocked before scanning so other
+                        // workers can pick up sites
+                        let Some(url) = urls.lock().unwrap().next() else {
+                            break;
+                        };
+                        let url = Self::normalize_entrypoint(url);
+                        let completed = pipeline.options.completed.as_deref();
+                        if completed.is_some_and(|c| c.is_completed(&url)) {
+                            debug!("({url}) skipping site completed by a previous run");
+                            continue;
+                        }
+                        let result = pipeline
+                            .scan_site(&walk_builder, url.clone(), &key_sender, &scripts_checked)
+                            .and_then(|()| match completed {
+                                // the site may not have been fully crawled
+                                _ if pipeline.is_cancelled() => Ok(()),
+                                Some(completed) => completed.mark_completed(&url),
+                                None => Ok(()),
+                            });
+                        if let Err(error) = result {
+                            errors.lock().unwrap().push(error);
+                        }
+                    });
+                }
+            });
+
+            *pipeline.cache_stats.lock().unwrap() = walk_builder.cache_stats();
+
+            // all workers have finished, so no more keys will be sent
+            debug!("Sending stop signal to API key collector");
+            key_sender
+                .send(ApiKeyMessage::Stop)
+                .into_diagnostic()
+                .context("Failed to close API key channel")
+                .unwrap();
+
+            debug!("Scraping completed");
+            errors.into_inner().unwrap()
+        });
+
+        (key_receiver, handle)
+    }
+
+    /// Scan each site in `urls`, blocking until every site has been scanned,
+    /// and collect everything found into a [`ScanReport`].
+    ///
+    /// Errors for sites that could not be scanned are logged and counted in
+    /// [`ScanStats::sites_failed`]. Use [`Pipeline::run`] to handle messages
+    /// as they arrive.
+    pub fn scan<U>(&self, urls: U) -> ScanReport
+    where
+        U: IntoIterator<Item = String> + Send + 'static,
+        U::IntoIter: Send,
+    {
+        let (receiver, handle) = self.run(urls);
+        let mut report = ScanReport::default();
+        for message in receiver {
+            match message {
+                ApiKeyMessage::Stop => break,
+                ApiKeyMessage::Keys(keys) => report.findings.extend(keys),
+                ApiKeyMessage::RecoverableFailure { kind, error } => {
+                    debug!("{error:?}");
+                    report.errors.push(kind);
+                }
+                ApiKeyMessage::DidScanScript => report.stats.scripts_checked += 1,
+                ApiKeyMessage::DidScrapePage { .. } => report.stats.pages_crawled += 1,
+                ApiKeyMessage::PageError { .. } => report.stats.page_errors += 1,
+                ApiKeyMessage::Endpoints { .. } | ApiKeyMessage::ScriptSource { .. } => {}
+            }
+        }
+        let errors = handle
+            .join()
+            .expect("Pipeline thread should have joined successfully");
+        for error in &errors {
+            warn!("{error:?}");
+        }
+        report.stats.keys_found = report.findings.len();
+        report.stats.sites_failed = errors.len();
+        report
+    }
+
+    /// Scan each site in `urls`, reporting found keys and crawl statistics
+    /// to `reporter`. Blocks until every site has been scanned, then flushes
+    /// the reporter.
+    ///
+    /// Scripts that could not be checked are logged. Returns errors for
+    /// sites that could not be scanned. Use [`Pipeline::run`] to handle
+    /// messages yourself.
+    #[cfg(feature = "report")]
+    pub fn report<U, H>(&self, urls: U, reporter: &Reporter<H>) -> Result<Vec<Error>>
+    where
+        U: IntoIterator<Item = String> + Send + 'static,
+        U::IntoIter: Send,
+        H: ReportHandler,
+    {
+        let (receiver, handle) = self.run(urls);
+        // keep draining the channel after a failed report so the scan can
+        // finish
+        let mut report_result = Ok(());
+        for message in receiver {
+            match message {
+                ApiKeyMessage::Stop => break,
+                ApiKeyMessage::Keys(keys) => {
+                    let reported = reporter.report_keys(&keys);
+                    if report_result.is_ok() {
+                        report_result = reported;
+                    }
+                }
+                ApiKeyMessage::RecoverableFailure { error, .. } => log::warn!("{error:?}"),
+                ApiKeyMessage::DidScanScript => reporter.record_scripts_checked(1),
+                ApiKeyMessage::DidScrapePage { .. } => reporter.record_pages_crawled(1),
+                ApiKeyMessage::PageError { .. } => reporter.record_page_errors(1),
+                ApiKeyMessage::Endpoints { .. } | ApiKeyMessage::ScriptSource { .. } => {}
+            }
+        }
+        let errors = handle
+            .join()
+            .expect("Pipeline thread should have joined successfully");
+        report_result?;
+        reporter.flush()?;
+        Ok(errors)
+    }
+
+    fn scan_site(
+        &self,
+        walk_builder: &WebsiteWalkBuilder,
+        url: String,
+        key_sender: &ApiKeySender,
+        scripts_checked: &Arc<AtomicUsize>,
+    ) -> Result<()> {
+        let options = &self.options;
+        let (tx_scripts, rx_scripts) = walk_builder.channel();
+        // cancelled by the collector once enough keys are found or scripts
+        // are checked, or along with the whole run
+        let limited = options.max_keys.is_some() || options.max_scripts.is_some();
+        let cancel = match (&options.cancel, limited) {
+            (Some(cancel), _) => Some(cancel.child()),
+            (None, true) => Some(CancellationToken::new()),
+            (None, false) => None,
+        };
+        // walkers share the script channel, which is closed once every locale
+        // has been crawled
+        let walkers: Vec<_> = walk_builder
+            .locale_builders()
+            .into_iter()
+            .map(|builder| match &cancel {
+                Some(cancel) => builder.with_cancel(cancel.clone()),
+                None => builder,
+            })
+            .map(|builder| builder.with_close_channel(false).build(tx_scripts.clone()))
+            .collect();
+        let collector =
+            ApiKeyCollector::new(options.config.clone(), rx_scripts, key_sender.clone())
+                .with_random_ua(options.random_ua)
+                .with_headers(options.headers.clone())
+                .with_json_scanning(options.scan_json)
+                .with_config_probing(options.config_paths.iter().cloned())
+                .with_endpoint_discovery(options.endpoints)
+                .with_tls_verification(options.verify_tls);
+        let collector = match &options.agent {
+            Some(agent) => collector.with_agent(agent.clone()),
+            None => collector,
+        };
+        let collector = match &options.ignore {
+            Some(ignore) => collector.with_ignore(Arc::clone(ignore)),
+            None => collector,
+        };
+        let collector = match options.extraction_timeout {
+            Some(timeout) => collector.with_extraction_timeout(timeout),
+            None => collector,
+        };
+        let collector = match (options.max_keys, &cancel) {
+            (Some(max_keys), Some(cancel)) => collector.with_max_keys(max_keys, cancel.clone()),
+            _ => collector,
+        };
+        let collector = match (options.max_scripts, cancel) {
+            (Some(max_scripts), Some(cancel)) => collector
+                .with_max_scripts(max_scripts, cancel)
+                .with_shared_script_count(Arc::clone(scripts_checked)),
+            _ => collector,
+        };
+
+        // Visit pages in the target site, sending found script urls over the
+        // script channel
+        let moved_url = url.clone();
+        let walk_handle = thread::spawn(move || {
+            let result = walkers
+                .into_iter()
+                .try_for_each(|walker| walker.walk(&moved_url));
+            // returned to the caller along with errors for other sites
+            if let Err(ref err) = result {
+                debug!("({moved_url}) {err}");
+            }
+            WebsiteWalker::signal_done(&tx_scripts)
+                .context("Failed to send stop signal over script channel")
+                .unwrap();
+
+            result
+        });
+
+        trace!("Starting API key collector thread");
+        let collector_handle = thread::spawn(move || collector.collect());
+
+        debug!("({url}) Waiting for collector thread to finish...");
+        collector_handle
+            .join()
+            .expect("ApiKeyCollector thread should have joined successfully");
+
+        debug!("({url}) Waiting for walker thread to finish...");
+        walk_handle
+            .join()
+            .expect("WebsiteWalker thread should have joined successfully")
+    }
+
+    /// Crawl a site and collect the scripts found on it, without checking them
+    /// for API keys.
+    pub fn list_scripts(&self, url: String) -> Result<Vec<Script>> {
+        let url = Self::normalize_entrypoint(url);
+        self.walk_builder().collect(url)
+    }
+
+    fn walk_builder(&self) -> WebsiteWalkBuilder {
+        let options = &self.options;
+        let mut builder = WebsiteWalkBuilder::default()
+            .with_random_ua(options.random_ua)
+            .with_headers(options.headers.clone())
+            .with_shared_cache(true)
+            .with_cookie_jar(true)
+            .with_tls_verification(options.verify_tls)
+            .with_locales(options.locales.iter().cloned());
+        if let Some(max_walks) = options.max_walks {
+            builder = builder.with_max_walks(max_walks);
+        }
+        if let Some(timeout) = options.timeout {
+            builder = builder.with_timeout(timeout);
+        }
+        if let Some(timeout) = options.timeout_connect {
+            builder = builder.with_timeout_connect(timeout);
+        }
+        if let Some(agent) = &options.agent {
+            builder = builder.with_agent(agent.clone());
+        }
+        builder
+    }
+
+    /// Use `https://` for URLs without a protocol.
+    fn normalize_entrypoint(url: String) -> String {
+        if !url.contains("://") {
+            String::from("https://") + &url
+        } else {
+            url
+        }
+    }
+}
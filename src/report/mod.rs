@@ -1,8 +1,17 @@
+mod baseline;
 mod builder;
+mod group;
 mod reporter;
 mod reporters;
 mod statistics;
+mod summary;
 
+pub use baseline::Baseline;
 pub use builder::ReporterBuilder;
+pub use group::KeyGroup;
 pub use reporter::Reporter;
-pub use reporters::{GraphicalReportHandler, JsonReportHandler, ReportHandler};
+pub use reporters::{
+    DirectoryReportHandler, GraphicalReportHandler, JsonReportHandler, ReportHandler, Theme,
+    JSON_SCHEMA_VERSION,
+};
+pub use summary::Summary;
@@ -1,11 +1,13 @@
+mod directory;
 mod graphical;
 mod json;
 
 use crate::ApiKeyError;
 use miette::Result;
 
-pub use graphical::GraphicalReportHandler;
-pub use json::JsonReportHandler;
+pub use directory::DirectoryReportHandler;
+pub use graphical::{GraphicalReportHandler, Theme};
+pub use json::{JsonReportHandler, JSON_SCHEMA_VERSION};
 
 pub type SyncBufWriter<W> = std::sync::Mutex<std::io::BufWriter<W>>;
 
@@ -1,20 +1,29 @@
 use dashmap::DashSet;
 use rustc_hash::FxBuildHasher;
 use std::sync::atomic::{AtomicUsize, Ordering};
+use url::{Position, Url};
+
+use super::Summary;
+use crate::ApiKeyError;
 
 #[derive(Debug, Default)]
 pub(super) struct Statistics {
     // TODO: record how many instances of each key were found?
     keys_found: DashSet<String, FxBuildHasher>,
+    /// `(host, rule id, secret)` of each key found
+    findings: DashSet<(String, String, String), FxBuildHasher>,
     scripts_checked: AtomicUsize,
     pages_crawled: AtomicUsize,
+    page_errors: AtomicUsize,
 }
 
 impl Statistics {
     #[inline]
-    pub fn record_keys_found<I: IntoIterator<Item = String>>(&self, keys: I) {
+    pub fn record_keys_found<'k, I: IntoIterator<Item = &'k ApiKeyError>>(&self, keys: I) {
         for key in keys {
-            self.keys_found.insert(key);
+            self.keys_found.insert(key.secret.clone());
+            self.findings
+                .insert((host_of(&key.url), key.rule_id.clone(), key.secret.clone()));
         }
     }
 
@@ -28,6 +37,11 @@ impl Statistics {
         self.pages_crawled.fetch_add(count, Ordering::Relaxed);
     }
 
+    #[inline]
+    pub fn record_page_errors(&self, count: usize) {
+        self.page_errors.fetch_add(count, Ordering::Relaxed);
+    }
+
     #[inline]
     pub fn keys_found(&self) -> usize {
         self.keys_found.len()
@@ -42,4 +56,28 @@ impl Statistics {
     pub fn pages_crawled(&self) -> usize {
         self.pages_crawled.load(Ordering::SeqCst)
     }
+
+    #[inline]
+    pub fn page_errors(&self) -> usize {
+        self.page_errors.load(Ordering::SeqCst)
+    }
+
+    pub fn summary(&self) -> Summary {
+        let mut summary = Summary::default();
+        for finding in self.findings.iter() {
+            let (host, rule_id, _) = finding.key();
+            *summary.by_host.entry(host.clone()).or_default() += 1;
+            *summary.by_rule.entry(rule_id.clone()).or_default() += 1;
+        }
+        summary
+    }
+}
+
+/// `example.com:8080` for `https://example.com:8080/main.js`. Falls back to
+/// the whole URL if it cannot be parsed.
+fn host_of(url: &str) -> String {
+    match Url::parse(url) {
+        Ok(url) if url.has_host() => url[Position::BeforeHost..Position::AfterPort].to_string(),
+        _ => url.to_string(),
+    }
 }
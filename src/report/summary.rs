@@ -0,0 +1,62 @@
+use std::{collections::BTreeMap, fmt};
+
+/// Number of distinct keys found on each script host and by each rule.
+///
+/// See [`Reporter::summary`](super::Reporter::summary).
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct Summary {
+    /// Keys found in scripts served from each host (and port, if not the
+    /// default), e.g. `cdn.example.com`.
+    pub by_host: BTreeMap<String, usize>,
+    /// Keys found by each rule, e.g. `aws-access-token`.
+    pub by_rule: BTreeMap<String, usize>,
+}
+
+impl Summary {
+    /// Returns `true` if no keys were found.
+    pub fn is_empty(&self) -> bool {
+        self.by_host.is_empty()
+    }
+}
+
+impl fmt::Display for Summary {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fn section(
+            f: &mut fmt::Formatter<'_>,
+            title: &str,
+            counts: &BTreeMap<String, usize>,
+        ) -> fmt::Result {
+            writeln!(f, "{title}:")?;
+            let width = counts.keys().map(String::len).max().unwrap_or_default();
+            for (name, count) in counts {
+                writeln!(f, "  {name:<width$}  {count}")?;
+            }
+            Ok(())
+        }
+
+        section(f, "Keys by host", &self.by_host)?;
+        section(f, "Keys by rule", &self.by_rule)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_display() {
+        let summary = Summary {
+            by_host: [("example.com".into(), 3), ("cdn.example.com".into(), 1)].into(),
+            by_rule: [("aws-access-token".into(), 4)].into(),
+        };
+        let expected = "\
+Keys by host:
+  cdn.example.com  1
+  example.com      3
+Keys by rule:
+  aws-access-token  4
+";
+        assert_eq!(summary.to_string(), expected);
+        assert!(Summary::default().is_empty());
+    }
+}
@@ -0,0 +1,195 @@
+// Copyright © 2024 Don Isaac
+//
+// This file is part of KeyHunter.
+//
+// KeyHunter is free software: you can redistribute it and/or modify it
+// under the terms of the GNU General Public License as published by the Free
+// Software Foundation, either version 3 of the License, or (at your option)
+// any later version.
+//
+// KeyHunter is distributed in the hope that it will be useful, but WITHOUT
+// ANY WARRANTY; without even the implied warranty of MERCHANTABILITY or
+// FITNESS FOR A PARTICULAR PURPOSE. See the GNU General Public License for
+// more details.
+//
+// You should have received a copy of the GNU General Public License along with
+// KeyHunter. If not, see <https://www.gnu.org/licenses/>.
+//! Structured timing records for each phase of a scan.
+//!
+//! Walking a page, downloading a script, and extracting keys from it each emit
+//! a pair of `trace`-level records with the [`TARGET`] target: one when the
+//! phase begins and one when it ends. Records carry structured `phase` and
+//! `url` fields, and end records also carry `elapsed_ms`, so they can be
+//! correlated with findings by any [`log`] implementation that supports
+//! key-value pairs.
+//!
+//! Enable them with e.g. `RUST_LOG=keyhunter::span=trace`.
+//!
+//! With the `tracing` feature, each phase is also a `trace`-level
+//! [`tracing`](https://docs.rs/tracing) span named `phase` with the same
+//! target and `phase` and `url` fields. Events emitted during a phase, e.g. by
+//! a custom [`Downloader`](crate::Downloader), are recorded within it.
+use std::time::Instant;
+
+use log::{log_enabled, trace, Level};
+
+/// The log target span records are emitted with.
+pub const TARGET: &str = "keyhunter::span";
+
+/// Phase names, used as the `phase` field of span records.
+pub mod phase {
+    /// Parsing a webpage and extracting its links and scripts.
+    pub const WALK_PAGE: &str = "walk_page";
+    /// Downloading a script.
+    pub const DOWNLOAD_SCRIPT: &str = "download_script";
+    /// Parsing a script or JSON file and extracting API keys from it.
+    pub const EXTRACT_KEYS: &str = "extract_keys";
+}
+
+/// Records the start of a phase when created and its end when dropped.
+#[must_use = "The phase ends when the span is dropped"]
+pub(crate) struct PhaseSpan {
+    /// [`None`] when span records are disabled, so that nothing is allocated.
+    inner: Option<(&'static str, String, Instant)>,
+    #[cfg(feature = "tracing")]
+    _span: tracing::span::EnteredSpan,
+}
+
+impl PhaseSpan {
+    pub fn enter(phase: &'static str, url: &str) -> Self {
+        #[cfg(feature = "tracing")]
+        let _span = tracing::trace_span!(target: TARGET, "phase", phase, url).entered();
+        if !log_enabled!(target: TARGET, Level::Trace) {
+            return Self {
+                inner: None,
+                #[cfg(feature = "tracing")]
+                _span,
+            };
+        }
+        trace!(target: TARGET, phase, url; "({url}) begin {phase}");
+        Self {
+            inner: Some((phase, url.to_string(), Instant::now())),
+            #[cfg(feature = "tracing")]
+            _span,
+        }
+    }
+}
+
+impl Drop for PhaseSpan {
+    fn drop(&mut self) {
+        let Some((phase, url, start)) = self.inner.take() else {
+            return;
+        };
+        let elapsed_ms = start.elapsed().as_millis() as u64;
+        let url = url.as_str();
+        trace!(target: TARGET, phase, url, elapsed_ms; "({url}) end {phase} in {elapsed_ms}ms");
+    }
+}
+
+#[cfg(all(test, feature = "tracing"))]
+mod test {
+    use std::{
+        fmt,
+        sync::{mpsc, Arc, Mutex},
+        time::Duration,
+    };
+
+    use tracing::{
+        field::{Field, Visit},
+        span::{Attributes, Id, Record},
+        Event, Metadata, Subscriber,
+    };
+
+    use super::*;
+    use crate::{
+        testing::{Response, TestServer},
+        ApiKeyCollector, Config, WebsiteWalkBuilder,
+    };
+
+    type Fields = Vec<(String, String)>;
+
+    /// Captures the fields of every phase span. Only installed for the
+    /// duration of a test, on that test's thread.
+    #[derive(Default, Clone)]
+    struct CaptureSpans(Arc<Mutex<Vec<Fields>>>);
+
+    struct CollectFields<'f>(&'f mut Fields);
+    impl Visit for CollectFields<'_> {
+        fn record_str(&mut self, field: &Field, value: &str) {
+            self.0.push((field.name().to_string(), value.to_string()));
+        }
+
+        fn record_debug(&mut self, field: &Field, value: &dyn fmt::Debug) {
+            self.0
+                .push((field.name().to_string(), format!("{value:?}")));
+        }
+    }
+
+    impl Subscriber for CaptureSpans {
+        fn enabled(&self, metadata: &Metadata<'_>) -> bool {
+            metadata.target() == TARGET
+        }
+
+        fn new_span(&self, span: &Attributes<'_>) -> Id {
+            let mut fields = Fields::new();
+            span.record(&mut CollectFields(&mut fields));
+            let mut spans = self.0.lock().unwrap();
+            spans.push(fields);
+            Id::from_u64(spans.len() as u64)
+        }
+
+        fn record(&self, _span: &Id, _values: &Record<'_>) {}
+
+        fn record_follows_from(&self, _span: &Id, _follows: &Id) {}
+
+        fn event(&self, _event: &Event<'_>) {}
+
+        fn enter(&self, _span: &Id) {}
+
+        fn exit(&self, _span: &Id) {}
+    }
+
+    fn field<'f>(fields: &'f Fields, key: &str) -> Option<&'f str> {
+        fields
+            .iter()
+            .find(|(k, _)| k == key)
+            .map(|(_, v)| v.as_str())
+    }
+
+    #[test]
+    fn test_scan_emits_spans() {
+        let page = r#"<html><head><script src="/app.js"></script></head></html>"#;
+        let server = TestServer::builder()
+            .route("/", Response::html(page))
+            .route("/app.js", Response::js("console.log('hello')"))
+            .start();
+
+        let capture = CaptureSpans::default();
+        tracing::subscriber::with_default(capture.clone(), || {
+            // walk and collect on this thread, where the subscriber is set
+            let (walker, rx_scripts) = WebsiteWalkBuilder::default()
+                .with_timeout(Duration::from_secs(1))
+                .with_close_channel(true)
+                .build_with_channel();
+            let (tx_keys, _rx_keys) = mpsc::channel();
+            walker.walk(&server.url()).unwrap();
+            ApiKeyCollector::new(Arc::new(Config::gitleaks()), rx_scripts, tx_keys).collect();
+        });
+
+        let spans = capture.0.lock().unwrap();
+        let page_url = format!("{}/", server.url().trim_end_matches('/'));
+        let script_url = server.url_for("/app.js");
+        for (phase, url) in [
+            (phase::WALK_PAGE, page_url.as_str()),
+            (phase::DOWNLOAD_SCRIPT, script_url.as_str()),
+            (phase::EXTRACT_KEYS, script_url.as_str()),
+        ] {
+            let count = spans
+                .iter()
+                .filter(|fields| field(fields, "phase") == Some(phase))
+                .filter(|fields| field(fields, "url") == Some(url))
+                .count();
+            assert_eq!(count, 1, "expected one {phase} span for {url}: {spans:?}");
+        }
+    }
+}
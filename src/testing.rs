@@ -0,0 +1,488 @@
+//! A tiny, blocking HTTP server for tests that must not touch the network,
+//! plus helpers for building findings without one.
+//!
+//! Integration tests can use it too with
+//! `#[path = "../src/testing.rs"] mod testing;`.
+#![allow(dead_code)]
+use std::{
+    collections::HashMap,
+    fs,
+    io::{BufRead, BufReader, Read, Write},
+    net::{SocketAddr, TcpListener, TcpStream},
+    path::{Component, Path, PathBuf},
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        mpsc, Arc, Mutex,
+    },
+    thread::{self, JoinHandle},
+    time::Duration,
+};
+
+use base64::{engine::general_purpose::STANDARD as BASE64_STANDARD, Engine as _};
+use keyhunter::{
+    ApiKeyCollector, ApiKeyError, ApiKeyExtractor, ApiKeyMessage, Config, WebsiteWalkBuilder,
+};
+use miette::NamedSource;
+use oxc::allocator::Allocator;
+use rustls::{
+    pki_types::{CertificateDer, PrivateKeyDer, PrivatePkcs8KeyDer},
+    ServerConfig, ServerConnection, StreamOwned,
+};
+
+/// Self-signed certificate for `localhost`, served by
+/// [`TestServerBuilder::tls`]. Valid until 2126.
+const TLS_CERT: &str = "
+MIIBmjCCAUGgAwIBAgIUUvVvVjBnOgsaoI9uBBjkcq2lwjwwCgYIKoZIzj0EAwIw
+FDESMBAGA1UEAwwJbG9jYWxob3N0MCAXDTI2MTAxNTIwMjMxNVoYDzIxMjYwOTIx
+MjAyMzE1WjAUMRIwEAYDVQQDDAlsb2NhbGhvc3QwWTATBgcqhkjOPQIBBggqhkjO
+PQMBBwNCAARtCga1a6W+SQAgKAdza9IMzfpV93ZsjLKxasUKFI3zkpvr1vcnzbO/
+htve9+0AeSFLvpxUwTjBxQle2FneOWRqo28wbTAdBgNVHQ4EFgQUnRmilzfjJqYj
+pVghSVFvl/H3O2gwHwYDVR0jBBgwFoAUnRmilzfjJqYjpVghSVFvl/H3O2gwDwYD
+VR0TAQH/BAUwAwEB/zAaBgNVHREEEzARgglsb2NhbGhvc3SHBH8AAAEwCgYIKoZI
+zj0EAwIDRwAwRAIgMldjo5P2iQPJO+BzCZU7F7Pq7jIXOOFy6pfA4gjgobECIF0n
+wujA61TLxao7eW+cIFRfJwoSvTzQHOzQMNJtpPjk";
+
+/// PKCS #8 private key for [`TLS_CERT`].
+const TLS_KEY: &str = "
+MIGHAgEAMBMGByqGSM49AgEGCCqGSM49AwEHBG0wawIBAQQgLz6AJrUA4KRcB7BU
+sjV6mxKxb2MQiVU0ophfJs3rd5+hRANCAARtCga1a6W+SQAgKAdza9IMzfpV93Zs
+jLKxasUKFI3zkpvr1vcnzbO/htve9+0AeSFLvpxUwTjBxQle2FneOWRq";
+
+/// A canned response served by [`TestServer`].
+#[derive(Debug, Clone)]
+pub struct Response {
+    pub status: u16,
+    pub headers: Vec<(String, String)>,
+    pub body: Vec<u8>,
+}
+
+impl Response {
+    pub fn new<B: Into<Vec<u8>>>(status: u16, content_type: &str, body: B) -> Self {
+        Self {
+            status,
+            headers: vec![("Content-Type".into(), content_type.into())],
+            body: body.into(),
+        }
+    }
+
+    pub fn html<B: Into<Vec<u8>>>(body: B) -> Self {
+        Self::new(200, "text/html; charset=utf-8", body)
+    }
+
+    pub fn js<B: Into<Vec<u8>>>(body: B) -> Self {
+        Self::new(200, "application/javascript", body)
+    }
+
+    pub fn status(status: u16) -> Self {
+        Self::new(status, "text/plain", "")
+    }
+
+    pub fn with_header<K: Into<String>, V: Into<String>>(mut self, key: K, value: V) -> Self {
+        self.headers.push((key.into(), value.into()));
+        self
+    }
+}
+
+/// A request received by [`TestServer`].
+#[derive(Debug, Clone)]
+pub struct Request {
+    pub method: String,
+    pub path: String,
+    /// Header names are lowercased.
+    pub headers: HashMap<String, String>,
+}
+
+impl Request {
+    pub fn header(&self, name: &str) -> Option<&str> {
+        self.headers
+            .get(&name.to_ascii_lowercase())
+            .map(String::as_str)
+    }
+}
+
+type Handler = Box<dyn Fn(&Request) -> Response + Send + Sync>;
+
+/// Serves canned responses on `127.0.0.1` at a random port until dropped.
+///
+/// Requests for unknown paths get a `404`.
+pub struct TestServer {
+    addr: SocketAddr,
+    tls: bool,
+    requests: Arc<Mutex<Vec<Request>>>,
+    stop: Arc<AtomicBool>,
+    handle: Option<JoinHandle<()>>,
+}
+
+#[derive(Default)]
+pub struct TestServerBuilder {
+    routes: Routes,
+    tls: bool,
+}
+
+#[derive(Default)]
+struct Routes {
+    handlers: HashMap<String, Handler>,
+    /// Static files are served from here when no handler matches
+    root: Option<PathBuf>,
+}
+
+impl TestServerBuilder {
+    /// Serve `response` for requests to `path`. Query strings are ignored when
+    /// matching paths.
+    pub fn route<S: Into<String>>(self, path: S, response: Response) -> Self {
+        self.route_fn(path, move |_| response.clone())
+    }
+
+    /// Compute a response for requests to `path`.
+    pub fn route_fn<S, F>(mut self, path: S, handler: F) -> Self
+    where
+        S: Into<String>,
+        F: Fn(&Request) -> Response + Send + Sync + 'static,
+    {
+        self.routes.handlers.insert(path.into(), Box::new(handler));
+        self
+    }
+
+    /// Serve files in `root` for paths without a route, like a static file
+    /// server. Requests for directories get their `index.html`.
+    pub fn dir<P: Into<PathBuf>>(mut self, root: P) -> Self {
+        self.routes.root = Some(root.into());
+        self
+    }
+
+    /// Serve HTTPS with a self-signed certificate for `localhost`, like a
+    /// staging site. Clients must skip certificate verification.
+    pub fn tls(mut self) -> Self {
+        self.tls = true;
+        self
+    }
+
+    pub fn start(self) -> TestServer {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = listener.local_addr().unwrap();
+        let requests: Arc<Mutex<Vec<Request>>> = Default::default();
+        let stop: Arc<AtomicBool> = Default::default();
+        let routes = Arc::new(self.routes);
+        let tls = self.tls.then(tls_config);
+
+        let handle = {
+            let requests = Arc::clone(&requests);
+            let stop = Arc::clone(&stop);
+            thread::spawn(move || {
+                for stream in listener.incoming() {
+                    if stop.load(Ordering::SeqCst) {
+                        break;
+                    }
+                    let Ok(stream) = stream else { continue };
+                    let routes = Arc::clone(&routes);
+                    let requests = Arc::clone(&requests);
+                    let tls = tls.clone();
+                    thread::spawn(move || match tls {
+                        Some(config) => {
+                            let Ok(conn) = ServerConnection::new(config) else {
+                                return;
+                            };
+                            let mut stream = StreamOwned::new(conn, stream);
+                            handle_connection(&mut stream, &routes, &requests);
+                            stream.conn.send_close_notify();
+                            let _ = stream.flush();
+                        }
+                        None => {
+                            let mut stream = stream;
+                            handle_connection(&mut stream, &routes, &requests);
+                        }
+                    });
+                }
+            })
+        };
+
+        TestServer {
+            addr,
+            tls: self.tls,
+            requests,
+            stop,
+            handle: Some(handle),
+        }
+    }
+}
+
+impl TestServer {
+    pub fn builder() -> TestServerBuilder {
+        TestServerBuilder::default()
+    }
+
+    /// e.g. `http://localhost:1234`, or `https://localhost:1234` when
+    /// serving HTTPS
+    pub fn url(&self) -> String {
+        let scheme = if self.tls { "https" } else { "http" };
+        format!("{scheme}://localhost:{}", self.addr.port())
+    }
+
+    /// Get an absolute URL to `path` on this server.
+    pub fn url_for(&self, path: &str) -> String {
+        format!("{}{}", self.url(), path)
+    }
+
+    /// All requests received so far, in order of arrival.
+    pub fn requests(&self) -> Vec<Request> {
+        self.requests.lock().unwrap().clone()
+    }
+
+    /// Requests received for `path`, ignoring query strings.
+    pub fn requests_for(&self, path: &str) -> Vec<Request> {
+        self.requests()
+            .into_iter()
+            .filter(|req| strip_query(&req.path) == path)
+            .collect()
+    }
+}
+
+impl Drop for TestServer {
+    fn drop(&mut self) {
+        self.stop.store(true, Ordering::SeqCst);
+        // unblock the accept loop
+        let _ = TcpStream::connect(self.addr);
+        if let Some(handle) = self.handle.take() {
+            let _ = handle.join();
+        }
+    }
+}
+
+/// Findings [`Config::gitleaks`] reports in `source_code`, as if it were
+/// downloaded from `url`.
+pub fn find_keys(url: &str, source_code: &str) -> Vec<ApiKeyError> {
+    find_keys_with(Config::gitleaks(), url, source_code)
+}
+
+/// Like [`find_keys`], but only `aws-access-token` findings. gitleaks' generic
+/// rules also match some of the `AKIA...` keys tests use.
+pub fn find_aws_keys(url: &str, source_code: &str) -> Vec<ApiKeyError> {
+    let mut keys = find_keys(url, source_code);
+    keys.retain(|key| key.rule_id == "aws-access-token");
+    keys
+}
+
+/// Findings `config` reports in `source_code`, as if it were downloaded from
+/// `url`.
+pub fn find_keys_with(config: Config, url: &str, source_code: &str) -> Vec<ApiKeyError> {
+    let config = Arc::new(config);
+    let extractor = ApiKeyExtractor::new(Arc::clone(&config));
+    let alloc = Allocator::default();
+    let url = Arc::new(url.to_string());
+    let source = Arc::new(NamedSource::new(url.as_str(), source_code.to_string()));
+
+    extractor
+        .extract_api_keys(&alloc, source_code)
+        .unwrap()
+        .into_iter()
+        .map(|key| ApiKeyError::new(key, Arc::clone(&url), &source, &config))
+        .collect()
+}
+
+/// Walk `server` from its root and scan every script found with
+/// [`Config::gitleaks`], returning the keys found. Walks time out after a
+/// second.
+pub fn scan_server<W, C>(
+    server: &TestServer,
+    configure_walker: W,
+    configure_collector: C,
+) -> Vec<ApiKeyError>
+where
+    W: FnOnce(WebsiteWalkBuilder) -> WebsiteWalkBuilder,
+    C: FnOnce(ApiKeyCollector) -> ApiKeyCollector,
+{
+    let (walker, rx_scripts) = configure_walker(
+        WebsiteWalkBuilder::default()
+            .with_timeout(Duration::from_secs(1))
+            .with_close_channel(true),
+    )
+    .build_with_channel();
+    let (tx_keys, rx_keys) = mpsc::channel();
+    let collector = configure_collector(ApiKeyCollector::new(
+        Arc::new(Config::gitleaks()),
+        rx_scripts,
+        tx_keys,
+    ));
+
+    let collector_handle = thread::spawn(move || collector.collect());
+    walker.walk(&server.url()).unwrap();
+    collector_handle.join().unwrap();
+
+    rx_keys
+        .try_iter()
+        .filter_map(|message| match message {
+            ApiKeyMessage::Keys(keys) => Some(keys),
+            _ => None,
+        })
+        .flatten()
+        .collect()
+}
+
+/// Collects reported findings instead of printing them
+#[cfg(feature = "report")]
+#[derive(Default)]
+pub struct RecordingHandler {
+    pub findings: Mutex<Vec<keyhunter::Finding>>,
+}
+
+#[cfg(feature = "report")]
+impl keyhunter::report::ReportHandler for &RecordingHandler {
+    fn report_key(&self, key: &ApiKeyError) -> miette::Result<()> {
+        self.findings
+            .lock()
+            .unwrap()
+            .push(keyhunter::Finding::from(key));
+        Ok(())
+    }
+}
+
+fn strip_query(path: &str) -> &str {
+    path.split_once('?').map_or(path, |(path, _)| path)
+}
+
+fn tls_config() -> Arc<ServerConfig> {
+    let decode = |base64: &str| {
+        let base64: String = base64.split_whitespace().collect();
+        BASE64_STANDARD.decode(base64).unwrap()
+    };
+    let cert = CertificateDer::from(decode(TLS_CERT));
+    let key = PrivateKeyDer::Pkcs8(PrivatePkcs8KeyDer::from(decode(TLS_KEY)));
+    let config =
+        ServerConfig::builder_with_provider(Arc::new(rustls::crypto::ring::default_provider()))
+            .with_safe_default_protocol_versions()
+            .unwrap()
+            .with_no_client_auth()
+            .with_single_cert(vec![cert], key)
+            .unwrap();
+    Arc::new(config)
+}
+
+fn handle_connection<S: Read + Write>(
+    stream: &mut S,
+    routes: &Routes,
+    requests: &Mutex<Vec<Request>>,
+) {
+    let mut reader = BufReader::new(&mut *stream);
+    let mut request_line = String::new();
+    if reader.read_line(&mut request_line).is_err() || request_line.is_empty() {
+        return;
+    }
+    let mut parts = request_line.split_whitespace();
+    let method = parts.next().unwrap_or_default().to_string();
+    let path = parts.next().unwrap_or_default().to_string();
+
+    let mut headers = HashMap::new();
+    loop {
+        let mut line = String::new();
+        if reader.read_line(&mut line).is_err() {
+            return;
+        }
+        let line = line.trim_end();
+        if line.is_empty() {
+            break;
+        }
+        if let Some((key, value)) = line.split_once(':') {
+            headers.insert(key.trim().to_ascii_lowercase(), value.trim().to_string());
+        }
+    }
+    // drain request bodies so clients don't see a reset connection
+    if let Some(len) = headers
+        .get("content-length")
+        .and_then(|len| len.parse::<u64>().ok())
+    {
+        let _ = std::io::copy(&mut reader.by_ref().take(len), &mut std::io::sink());
+    }
+
+    let request = Request {
+        method,
+        path,
+        headers,
+    };
+    requests.lock().unwrap().push(request.clone());
+
+    let path = strip_query(&request.path);
+    let response = match routes.handlers.get(path) {
+        Some(handler) => handler(&request),
+        None => routes
+            .root
+            .as_deref()
+            .and_then(|root| serve_file(root, path))
+            .unwrap_or_else(|| Response::status(404)),
+    };
+
+    let _ = write_response(stream, &response);
+}
+
+fn serve_file(root: &Path, path: &str) -> Option<Response> {
+    let relative = Path::new(path.trim_start_matches('/'));
+    // don't serve files outside of root
+    if relative
+        .components()
+        .any(|component| !matches!(component, Component::Normal(_)))
+    {
+        return None;
+    }
+    let mut file = root.join(relative);
+    if file.is_dir() {
+        file.push("index.html");
+    }
+    let body = fs::read(&file).ok()?;
+    let content_type = match file.extension().and_then(|ext| ext.to_str()) {
+        Some("html" | "htm") => "text/html; charset=utf-8",
+        Some("js" | "mjs") => "application/javascript",
+        Some("css") => "text/css",
+        Some("json") => "application/json",
+        Some("svg") => "image/svg+xml",
+        Some("png") => "image/png",
+        Some("jpg" | "jpeg") => "image/jpeg",
+        _ => "application/octet-stream",
+    };
+    Some(Response::new(200, content_type, body))
+}
+
+fn write_response<S: Write>(stream: &mut S, response: &Response) -> std::io::Result<()> {
+    write!(
+        stream,
+        "HTTP/1.1 {} {}\r\n",
+        response.status,
+        reason(response.status)
+    )?;
+    for (key, value) in &response.headers {
+        write!(stream, "{key}: {value}\r\n")?;
+    }
+    let has_transfer_encoding = response
+        .headers
+        .iter()
+        .any(|(key, _)| key.eq_ignore_ascii_case("transfer-encoding"));
+    if has_transfer_encoding {
+        // only chunked encoding is supported
+        write!(stream, "Connection: close\r\n\r\n")?;
+        if !response.body.is_empty() {
+            write!(stream, "{:x}\r\n", response.body.len())?;
+            stream.write_all(&response.body)?;
+            write!(stream, "\r\n")?;
+        }
+        write!(stream, "0\r\n\r\n")?;
+    } else {
+        write!(
+            stream,
+            "Content-Length: {}\r\nConnection: close\r\n\r\n",
+            response.body.len()
+        )?;
+        stream.write_all(&response.body)?;
+    }
+    stream.flush()
+}
+
+fn reason(status: u16) -> &'static str {
+    match status {
+        200 => "OK",
+        301 => "Moved Permanently",
+        302 => "Found",
+        401 => "Unauthorized",
+        403 => "Forbidden",
+        404 => "Not Found",
+        500 => "Internal Server Error",
+        _ => "Unknown",
+    }
+}
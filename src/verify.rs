@@ -0,0 +1,268 @@
+//! Live verification of found API keys.
+//
+//  Copyright © 2024 Don Isaac
+//
+//  This file is part of KeyHunter.
+//
+//  KeyHunter is free software: you can redistribute it and/or modify it
+//  under the terms of the GNU General Public License as published by the Free
+//  Software Foundation, either version 3 of the License, or (at your option)
+//  any later version.
+//
+//  KeyHunter is distributed in the hope that it will be useful, but WITHOUT
+//  ANY WARRANTY; without even the implied warranty of MERCHANTABILITY or
+//  FITNESS FOR A PARTICULAR PURPOSE. See the GNU General Public License for
+//  more details.
+//
+//  You should have received a copy of the GNU General Public License along with
+//  KeyHunter. If not, see <https://www.gnu.org/licenses/>.
+//!
+//! ## ⚠️ Risks
+//! Verifying a key means *using* it: an authenticated request is sent to the
+//! provider on the key owner's behalf. Even though only read-only, no-op
+//! endpoints are used, this may show up in the owner's audit logs, count
+//! against their rate limits, or trip fraud detection. Only verify keys you are
+//! authorized to use, e.g. keys belonging to your own organization.
+//!
+//! Requests are rate-limited (see [`KeyVerifier::with_rate_limit`]) and keys
+//! for unrecognized providers are never sent anywhere.
+use std::{
+    collections::HashMap,
+    sync::Mutex,
+    thread,
+    time::{Duration, Instant},
+};
+
+use log::{debug, warn};
+use ureq::{Agent, AgentBuilder};
+
+use crate::ApiKeyError;
+
+/// Providers whose keys can be verified.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub enum Provider {
+    Stripe,
+    SendGrid,
+}
+
+impl Provider {
+    /// Get the provider that issues keys found by a rule.
+    ///
+    /// Returns [`None`] for rules whose keys cannot be verified.
+    pub fn from_rule_id(rule_id: &str) -> Option<Self> {
+        match rule_id {
+            "stripe-access-token" => Some(Self::Stripe),
+            "sendgrid-api-token" => Some(Self::SendGrid),
+            _ => None,
+        }
+    }
+
+    fn default_base_url(self) -> &'static str {
+        match self {
+            Self::Stripe => "https://api.stripe.com",
+            Self::SendGrid => "https://api.sendgrid.com",
+        }
+    }
+
+    /// A read-only endpoint that requires authentication.
+    fn endpoint(self) -> &'static str {
+        match self {
+            Self::Stripe => "/v1/balance",
+            Self::SendGrid => "/v3/scopes",
+        }
+    }
+}
+
+/// Checks if found API keys are live by making a minimal authenticated request
+/// to the provider that issued them.
+///
+/// Read the [module-level docs](self) before using this.
+#[derive(Debug)]
+pub struct KeyVerifier {
+    agent: Agent,
+    /// Minimum time between two verification requests
+    min_interval: Duration,
+    last_request: Mutex<Option<Instant>>,
+    /// Overrides for provider API base URLs
+    base_urls: HashMap<Provider, String>,
+}
+
+impl Default for KeyVerifier {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl KeyVerifier {
+    const DEFAULT_MIN_INTERVAL: Duration = Duration::from_secs(1);
+
+    pub fn new() -> Self {
+        let agent = AgentBuilder::new().timeout(Duration::from_secs(10)).build();
+        Self {
+            agent,
+            min_interval: Self::DEFAULT_MIN_INTERVAL,
+            last_request: Mutex::new(None),
+            base_urls: HashMap::new(),
+        }
+    }
+
+    /// Wait at least `min_interval` between verification requests.
+    ///
+    /// Default 1 second.
+    #[must_use]
+    pub fn with_rate_limit(mut self, min_interval: Duration) -> Self {
+        self.min_interval = min_interval;
+        self
+    }
+
+    /// Send verification requests for `provider` to `base_url` instead of the
+    /// provider's public API. Useful for proxies and tests.
+    #[must_use]
+    pub fn with_base_url<S: Into<String>>(mut self, provider: Provider, base_url: S) -> Self {
+        self.base_urls.insert(provider, base_url.into());
+        self
+    }
+
+    /// Verify a found key, recording the result in [`ApiKeyError::verified`].
+    ///
+    /// Returns the verification result. See [`KeyVerifier::verify_secret`].
+    pub fn verify(&self, key: &mut ApiKeyError) -> Option<bool> {
+        let verified = Provider::from_rule_id(&key.rule_id)
+            .and_then(|provider| self.verify_secret(provider, &key.secret));
+        key.verified = verified;
+        verified
+    }
+
+    /// Check if `secret` is a live key for `provider`.
+    ///
+    /// Returns `Some(true)` if the provider accepted the key, `Some(false)` if
+    /// it was rejected, and [`None`] if the result could not be determined
+    /// (e.g. due to a network error).
+    pub fn verify_secret(&self, provider: Provider, secret: &str) -> Option<bool> {
+        self.wait_for_rate_limit();
+
+        let base_url = self
+            .base_urls
+            .get(&provider)
+            .map_or(provider.default_base_url(), String::as_str);
+        let url = format!("{}{}", base_url.trim_end_matches('/'), provider.endpoint());
+        debug!("({url}) verifying {provider:?} key");
+
+        match self
+            .agent
+            .get(&url)
+            .set("Authorization", &format!("Bearer {secret}"))
+            .call()
+        {
+            Ok(_) => Some(true),
+            Err(ureq::Error::Status(401 | 403, _)) => Some(false),
+            Err(e) => {
+                warn!("({url}) could not verify {provider:?} key: {e}");
+                None
+            }
+        }
+    }
+
+    fn wait_for_rate_limit(&self) {
+        let mut last_request = self.last_request.lock().unwrap();
+        if let Some(last) = *last_request {
+            let elapsed = last.elapsed();
+            if elapsed < self.min_interval {
+                thread::sleep(self.min_interval - elapsed);
+            }
+        }
+        *last_request = Some(Instant::now());
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::testing::{Response, TestServer};
+
+    const VALID: &str = "sk_live_0123456789abcdef";
+
+    fn mock_provider(endpoint: &'static str) -> TestServer {
+        TestServer::builder()
+            .route_fn(endpoint, |req| {
+                if req.header("Authorization") == Some(&format!("Bearer {VALID}")) {
+                    Response::new(200, "application/json", "{}")
+                } else {
+                    Response::status(401)
+                }
+            })
+            .start()
+    }
+
+    #[test]
+    fn test_verify_stripe() {
+        let server = mock_provider("/v1/balance");
+        let verifier = KeyVerifier::new()
+            .with_rate_limit(Duration::ZERO)
+            .with_base_url(Provider::Stripe, server.url());
+
+        assert_eq!(verifier.verify_secret(Provider::Stripe, VALID), Some(true));
+        assert_eq!(
+            verifier.verify_secret(Provider::Stripe, "sk_live_revoked"),
+            Some(false)
+        );
+        assert_eq!(server.requests_for("/v1/balance").len(), 2);
+    }
+
+    #[test]
+    fn test_verify_sendgrid() {
+        let server = mock_provider("/v3/scopes");
+        let verifier = KeyVerifier::new()
+            .with_rate_limit(Duration::ZERO)
+            .with_base_url(Provider::SendGrid, server.url());
+
+        assert_eq!(
+            verifier.verify_secret(Provider::SendGrid, VALID),
+            Some(true)
+        );
+        assert_eq!(
+            verifier.verify_secret(Provider::SendGrid, "SG.nope"),
+            Some(false)
+        );
+    }
+
+    #[test]
+    fn test_verify_unknown_result() {
+        let server = TestServer::builder()
+            .route("/v1/balance", Response::status(500))
+            .start();
+        let verifier = KeyVerifier::new()
+            .with_rate_limit(Duration::ZERO)
+            .with_base_url(Provider::Stripe, server.url());
+
+        assert_eq!(verifier.verify_secret(Provider::Stripe, VALID), None);
+    }
+
+    #[test]
+    fn test_rate_limit() {
+        let server = mock_provider("/v1/balance");
+        let verifier = KeyVerifier::new()
+            .with_rate_limit(Duration::from_millis(100))
+            .with_base_url(Provider::Stripe, server.url());
+
+        let start = Instant::now();
+        for _ in 0..3 {
+            verifier.verify_secret(Provider::Stripe, VALID);
+        }
+        assert!(start.elapsed() >= Duration::from_millis(200));
+    }
+
+    #[test]
+    fn test_provider_from_rule_id() {
+        assert_eq!(
+            Provider::from_rule_id("stripe-access-token"),
+            Some(Provider::Stripe)
+        );
+        assert_eq!(
+            Provider::from_rule_id("sendgrid-api-token"),
+            Some(Provider::SendGrid)
+        );
+        assert_eq!(Provider::from_rule_id("generic-api-key"), None);
+    }
+}
@@ -1,4 +1,7 @@
 mod website;
 // TODO: file walker
 
-pub use website::{Script, ScriptMessage, ScriptReceiver, WebsiteWalkBuilder, WebsiteWalker};
+pub use website::{
+    CacheStats, CancellationToken, PageOffset, Script, ScriptMessage, ScriptReceiver,
+    WebsiteWalkBuilder, WebsiteWalker,
+};
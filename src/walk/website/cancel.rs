@@ -0,0 +1,71 @@
+use std::sync::{
+    atomic::{AtomicBool, Ordering},
+    Arc,
+};
+
+/// A handle for stopping a walk from another thread.
+///
+/// Tokens are cheap to clone, and all clones share the same state. Once
+/// cancelled, walkers using the token stop visiting new pages, finish the
+/// walk, and close their script channel.
+///
+/// ## Example
+/// ```
+/// use keyhunter::{CancellationToken, WebsiteWalkBuilder};
+///
+/// let token = CancellationToken::new();
+/// let builder = WebsiteWalkBuilder::new().with_cancel(token.clone());
+///
+/// // e.g. from a UI thread or a timer
+/// token.cancel();
+/// assert!(token.is_cancelled());
+/// ```
+#[derive(Debug, Default, Clone)]
+pub struct CancellationToken {
+    cancelled: Arc<AtomicBool>,
+    /// Cancelling the parent also cancels this token
+    parent: Option<Arc<CancellationToken>>,
+}
+
+impl CancellationToken {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Create a token that is cancelled along with this one, but can also be
+    /// cancelled on its own without affecting this token.
+    ///
+    /// ## Example
+    /// ```
+    /// use keyhunter::CancellationToken;
+    ///
+    /// let all_sites = CancellationToken::new();
+    /// let (site_a, site_b) = (all_sites.child(), all_sites.child());
+    ///
+    /// site_a.cancel();
+    /// assert!(!all_sites.is_cancelled() && !site_b.is_cancelled());
+    ///
+    /// all_sites.cancel();
+    /// assert!(site_b.is_cancelled());
+    /// ```
+    pub fn child(&self) -> Self {
+        Self {
+            cancelled: Arc::default(),
+            parent: Some(Arc::new(self.clone())),
+        }
+    }
+
+    /// Stop all walks using this token.
+    pub fn cancel(&self) {
+        self.cancelled.store(true, Ordering::Relaxed);
+    }
+
+    #[inline]
+    pub fn is_cancelled(&self) -> bool {
+        self.cancelled.load(Ordering::Relaxed)
+            || self
+                .parent
+                .as_ref()
+                .is_some_and(|parent| parent.is_cancelled())
+    }
+}
@@ -22,6 +22,27 @@ impl<T> From<NotHtmlDiagnostic> for Result<T> {
     }
 }
 
+#[derive(Debug, Error, Diagnostic)]
+#[error("{url} responded with an error page ({reason}) despite a successful status code")]
+#[diagnostic(help("The page was skipped so its links are not crawled"))]
+pub struct SoftErrorPageDiagnostic {
+    url: String,
+    reason: &'static str,
+}
+impl SoftErrorPageDiagnostic {
+    pub fn new<S: Into<String>>(url: S, reason: &'static str) -> Self {
+        Self {
+            url: url.into(),
+            reason,
+        }
+    }
+}
+impl<T> From<SoftErrorPageDiagnostic> for Result<T> {
+    fn from(val: SoftErrorPageDiagnostic) -> Self {
+        Err(val.into())
+    }
+}
+
 #[derive(Debug, Error, Diagnostic)]
 #[error("Server responded to requests for {url} with no content")]
 pub struct NoContentDiagnostic {
@@ -38,6 +59,41 @@ impl<T> From<NoContentDiagnostic> for Result<T> {
     }
 }
 
+#[derive(Debug, Error, Diagnostic)]
+#[error("{url} redirected to {location}, which is outside the crawl's allowed domains")]
+pub struct OutOfScopeRedirectDiagnostic {
+    url: String,
+    location: String,
+}
+impl OutOfScopeRedirectDiagnostic {
+    pub fn new<S: Into<String>, L: Into<String>>(url: S, location: L) -> Self {
+        Self {
+            url: url.into(),
+            location: location.into(),
+        }
+    }
+}
+
+#[derive(Debug, Error, Diagnostic)]
+#[error("{url} redirected more than {max_redirects} times")]
+pub struct TooManyRedirectsDiagnostic {
+    url: String,
+    max_redirects: u32,
+}
+impl TooManyRedirectsDiagnostic {
+    pub fn new<S: Into<String>>(url: S, max_redirects: u32) -> Self {
+        Self {
+            url: url.into(),
+            max_redirects,
+        }
+    }
+}
+impl<T> From<TooManyRedirectsDiagnostic> for Result<T> {
+    fn from(val: TooManyRedirectsDiagnostic) -> Self {
+        Err(val.into())
+    }
+}
+
 #[derive(Debug, Error, Diagnostic)]
 pub struct WalkFailedDiagnostic {
     url: String,
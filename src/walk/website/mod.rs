@@ -1,3 +1,4 @@
+mod cancel;
 mod dom_walker;
 mod error;
 mod url_extractor;
@@ -5,5 +6,7 @@ mod walk;
 mod walk_builder;
 mod walk_cache;
 
-pub use walk::{Script, ScriptMessage, ScriptReceiver, WebsiteWalker};
+pub use cancel::CancellationToken;
+pub use walk::{PageOffset, Script, ScriptMessage, ScriptReceiver, WebsiteWalker};
 pub use walk_builder::WebsiteWalkBuilder;
+pub use walk_cache::CacheStats;
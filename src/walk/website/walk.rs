@@ -18,7 +18,9 @@ use log::{debug, trace, warn};
 use miette::{Context as _, Error, IntoDiagnostic as _, Result};
 
 use std::{
+    fs::File,
     num::NonZeroUsize,
+    path::PathBuf,
     sync::{
         atomic::{AtomicBool, AtomicUsize, Ordering},
         mpsc, Arc, Mutex, OnceLock,
@@ -26,19 +28,32 @@ use std::{
 };
 
 use rayon::prelude::*;
-use ureq::Agent;
+use rustc_hash::FxHashMap;
+use ureq::{Agent, Response};
 use url::Url;
 
 use super::{
     dom_walker::DomWalker,
-    error::{NoContentDiagnostic, NotHtmlDiagnostic},
+    error::{
+        NoContentDiagnostic, NotHtmlDiagnostic, OutOfScopeRedirectDiagnostic,
+        SoftErrorPageDiagnostic, TooManyRedirectsDiagnostic,
+    },
     url_extractor::UrlExtractor,
     walk_cache::WalkCache,
-    WebsiteWalkBuilder,
+    CancellationToken, WebsiteWalkBuilder,
+};
+use crate::{
+    http::{is_generic_content_type, sniff_html, sniff_soft_error_page},
+    span::{phase, PhaseSpan},
+    walk::website::error::WalkFailedDiagnostic,
+    Pattern,
 };
-use crate::walk::website::error::WalkFailedDiagnostic;
 
-pub type ScriptSender = mpsc::Sender<ScriptMessage>;
+/// Sends scripts from a [`WebsiteWalker`] to its consumer.
+///
+/// The channel is bounded so walkers block instead of queueing scripts when
+/// their consumer falls behind. See [`WebsiteWalkBuilder::with_channel_capacity`].
+pub type ScriptSender = mpsc::SyncSender<ScriptMessage>;
 pub type ScriptReceiver = mpsc::Receiver<ScriptMessage>;
 
 // TODO: use Arc for embedded page urls
@@ -46,8 +61,49 @@ pub type ScriptReceiver = mpsc::Receiver<ScriptMessage>;
 pub enum Script {
     /// A JS script fetchable at some URL
     Url(Arc<Url>),
-    /// JS embedded in a `<script>` tag within HTML
-    Embedded(/* source code */ String, /* page url */ Arc<Url>),
+    /// JS embedded in a `<script>` tag within HTML. The position is [`None`]
+    /// when the script's source code does not appear verbatim in the page,
+    /// e.g. for `data:` URIs.
+    Embedded(
+        /* source code */ String,
+        /* page url */ Arc<Url>,
+        /* position in page */ Option<PageOffset>,
+    ),
+}
+
+/// Where an embedded script starts within the HTML page it was found in.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct PageOffset {
+    /// Byte offset of the script's first character
+    pub offset: usize,
+    /// 0-based line the script starts on
+    pub line: usize,
+    /// 0-based column, in characters, the script starts on
+    pub column: usize,
+}
+
+impl PageOffset {
+    /// Find the position of `offset` within `html`.
+    pub fn new(html: &str, offset: usize) -> Self {
+        let before = &html[..offset];
+        let line_start = before.rfind('\n').map_or(0, |i| i + 1);
+        Self {
+            offset,
+            line: before.matches('\n').count(),
+            column: before[line_start..].chars().count(),
+        }
+    }
+
+    /// Prefix a script's source code with whitespace so that it starts on the
+    /// same line and column as it does in its page. Spans within the padded
+    /// source then report line numbers relative to the page.
+    pub fn pad(&self, source: &str) -> String {
+        let mut padded = String::with_capacity(self.line + self.column + source.len());
+        padded.extend(std::iter::repeat('\n').take(self.line));
+        padded.extend(std::iter::repeat(' ').take(self.column));
+        padded.push_str(source);
+        padded
+    }
 }
 impl From<Url> for Script {
     fn from(url: Url) -> Self {
@@ -57,8 +113,28 @@ impl From<Url> for Script {
 
 #[derive(Debug, Clone)]
 pub enum ScriptMessage {
-    Scripts(Vec<Script>),
-    DidWalkPage,
+    Scripts {
+        /// The page the scripts were found on, if known
+        referrer: Option<Arc<Url>>,
+        scripts: Vec<Script>,
+        /// HTML of the page the scripts were found on. Only present when
+        /// `scripts` contains [`Script::Embedded`] scripts found within it,
+        /// whose keys are reported against the page instead of the script
+        /// alone.
+        page: Option<Arc<str>>,
+    },
+    /// A page was fetched and its scripts and links were extracted. Sent
+    /// before the page's [`ScriptMessage::Scripts`].
+    DidWalkPage {
+        url: Arc<Url>,
+        /// Size of the page's HTML, in bytes
+        bytes: usize,
+    },
+    /// A page could not be fetched. Its scripts and links were not checked.
+    PageError {
+        url: Arc<Url>,
+        error: Arc<Error>,
+    },
     Done,
 }
 impl IntoIterator for ScriptMessage {
@@ -67,8 +143,10 @@ impl IntoIterator for ScriptMessage {
 
     fn into_iter(self) -> Self::IntoIter {
         match self {
-            ScriptMessage::Scripts(scripts) => scripts.into_iter(),
-            ScriptMessage::Done | ScriptMessage::DidWalkPage => vec![].into_iter(),
+            ScriptMessage::Scripts { scripts, .. } => scripts.into_iter(),
+            ScriptMessage::Done
+            | ScriptMessage::DidWalkPage { .. }
+            | ScriptMessage::PageError { .. } => vec![].into_iter(),
         }
     }
 }
@@ -76,9 +154,11 @@ impl IntoIterator for ScriptMessage {
 #[derive(Debug)]
 pub struct WebsiteWalker {
     /// Found URLs of JS scripts are sent over this channel
-    sender: mpsc::Sender<ScriptMessage>,
+    sender: ScriptSender,
     /// ureq agent for making HTTP requests
     agent: Agent,
+    /// Where to save cookies once the walk is done
+    cookie_store_path: Option<PathBuf>,
     /// Random user agent to make us look like a browser
     // ua: Option<&'static str>,
     headers: Vec<(String, String)>,
@@ -105,6 +185,19 @@ pub struct WebsiteWalker {
     ///
     /// Default `true`
     close_channel_when_done: bool,
+    /// Maximum number of redirects followed when checking redirect scope
+    max_redirects: u32,
+    /// Follow redirects manually, skipping those that leave whitelisted
+    /// domains
+    check_redirect_scope: bool,
+    /// Stops the walk early when cancelled
+    cancel: CancellationToken,
+    /// Only crawl links whose path matches one of these
+    include_patterns: Vec<Pattern>,
+    /// Never crawl links whose path matches one of these
+    exclude_patterns: Vec<Pattern>,
+    /// Only crawl links whose path starts with this prefix
+    path_prefix: Option<String>,
 }
 
 impl WebsiteWalker {
@@ -118,6 +211,7 @@ impl WebsiteWalker {
 
         Self {
             agent,
+            cookie_store_path: builder.cookie_store_path.clone(),
             headers,
             sender,
             in_progress: 0.into(),
@@ -128,6 +222,12 @@ impl WebsiteWalker {
             base_url: Default::default(),
             cache: builder.cache.clone().unwrap_or_default(),
             close_channel_when_done: builder.close_channel_when_done,
+            max_redirects: builder.max_redirects,
+            check_redirect_scope: builder.check_redirect_scope,
+            cancel: builder.cancel.clone().unwrap_or_default(),
+            include_patterns: builder.include_patterns.clone(),
+            exclude_patterns: builder.exclude_patterns.clone(),
+            path_prefix: builder.path_prefix.clone(),
         }
     }
 
@@ -135,8 +235,29 @@ impl WebsiteWalker {
         &self.sender
     }
 
+    /// Tell whoever is receiving scripts (e.g. an [`ApiKeyCollector`]) that no
+    /// more walks will be performed.
+    ///
+    /// Walkers built with [`WebsiteWalkBuilder::with_close_channel`] set to
+    /// `false` do not send [`ScriptMessage::Done`] when they finish, so that
+    /// many walks can share one script channel. Call this once after the last
+    /// walk is done to close the pipeline. Otherwise, receivers only stop once
+    /// every sender has been dropped.
+    ///
+    /// Returns an error if the receiver has already been dropped.
+    ///
+    /// [`ApiKeyCollector`]: crate::ApiKeyCollector
+    pub fn signal_done(sender: &ScriptSender) -> Result<()> {
+        sender
+            .send(ScriptMessage::Done)
+            .into_diagnostic()
+            .context("Failed to signal done: script channel is closed")
+    }
+
     pub fn walk(mut self, url: &str) -> Result<()> {
-        let url = url.trim().trim_end_matches('/');
+        // trailing slashes are kept so that relative URLs on the page resolve
+        // correctly, e.g. `../app.js` on `/a/b/`
+        let url = url.trim();
         let parsed = Url::parse(url)
             .into_diagnostic()
             .context(format!("Failed to start walk at {url}"))?;
@@ -156,10 +277,38 @@ impl WebsiteWalker {
 
         debug!("({parsed}) Starting walk ");
         // returns Err if entry url is not reachable, not html, etc.
-        self.visit_many(vec![parsed])
+        let result = self.visit_many(vec![parsed]);
+        self.save_cookies();
+        result
+    }
+
+    fn save_cookies(&self) {
+        let Some(path) = self.cookie_store_path.as_ref() else {
+            return;
+        };
+
+        let saved = File::create(path).into_diagnostic().and_then(|mut file| {
+            self.agent
+                .cookie_store()
+                .save_incl_expired_and_nonpersistent_json(&mut file)
+                .map_err(|e| Error::msg(e.to_string()))
+        });
+        match saved {
+            Ok(()) => debug!("Saved cookies to {}", path.display()),
+            Err(e) => warn!(
+                "{:?}",
+                e.context(format!("Failed to save cookies to {}", path.display()))
+            ),
+        }
     }
 
     fn visit_many(&mut self, mut urls: Vec<Url>) -> Result<(), Error> {
+        if self.cancel.is_cancelled() {
+            debug!("Finishing walk, walk was cancelled");
+            self.finish();
+            return Ok(());
+        }
+
         let pages_to_visit = self.reserve_walk_count(urls.len());
         if pages_to_visit == 0 && *self.in_progress.get_mut().unwrap() == 0 {
             debug!("Finishing walk, No more pages to visit");
@@ -175,7 +324,14 @@ impl WebsiteWalker {
                 if self.cache.has_seen_url(&url) {
                     return Ok(());
                 }
-                let webpage = self.get_webpage(url.as_str())?;
+                let webpage = self.get_webpage(url.as_str()).inspect_err(|e| {
+                    // reports aren't Clone, so only the message is forwarded
+                    self.send_page_error(&url, Error::msg(e.to_string()));
+                })?;
+                let Some(webpage) = webpage else {
+                    self.on_visit_end(1);
+                    return Ok(());
+                };
                 let result = self.walk_rec(&url, &webpage);
                 self.on_visit_end(1);
                 result
@@ -187,15 +343,15 @@ impl WebsiteWalker {
                     .filter(|url| self.is_whitelisted_link(url) && !self.cache.has_seen_url(url))
                     .take(pages_to_visit)
                     .par_bridge()
-                    .map(|url| {
-                        self.get_webpage(url.as_str())
-                            .map(|webpage| (url, webpage))
-                            .map_err(|e| {
-                                warn!("{e:?}");
-                                e
-                            })
+                    .filter(|_| !self.cancel.is_cancelled())
+                    .filter_map(|url| match self.get_webpage(url.as_str()) {
+                        Ok(webpage) => webpage.map(|webpage| (url, webpage)),
+                        Err(e) => {
+                            warn!("{e:?}");
+                            self.send_page_error(&url, e);
+                            None
+                        }
                     })
-                    .filter_map(Result::ok)
                     .collect::<Vec<_>>();
 
                 if urls_and_webpages.is_empty() {
@@ -258,38 +414,49 @@ impl WebsiteWalker {
     }
 
     fn walk_rec(&mut self, url: &Arc<Url>, webpage: &str) -> Result<(), Error> {
-        trace!("Building DOM walker for '{url}'");
-        let dom_walker = DomWalker::new(webpage).context("Failed to parse HTML")?;
+        let pages = {
+            // ends before linked pages are visited
+            let _span = PhaseSpan::enter(phase::WALK_PAGE, url.as_str());
+            trace!("Building DOM walker for '{url}'");
+            let dom_walker = DomWalker::new(webpage).context("Failed to parse HTML")?;
 
-        trace!("Extracting links and scripts for '{url}'");
-        let mut url_visitor = UrlExtractor::new(self.base_url.get().unwrap(), Arc::clone(url));
-        dom_walker.walk(&mut url_visitor);
-        let (pages, scripts) = url_visitor.into_inner();
+            trace!("Extracting links and scripts for '{url}'");
+            let mut url_visitor = UrlExtractor::new(Arc::clone(url), webpage);
+            dom_walker.walk(&mut url_visitor);
+            let (mut pages, scripts, integrity) = url_visitor.into_inner();
+            pages.retain(|page| self.is_included_path(page));
 
-        self.send(ScriptMessage::DidWalkPage);
-        self.send_scripts(scripts);
+            self.send(ScriptMessage::DidWalkPage {
+                url: Arc::clone(url),
+                bytes: webpage.len(),
+            });
+            self.send_scripts(url, webpage, scripts, &integrity);
+            pages
+        };
 
         self.visit_many(pages)
     }
 
-    fn get_webpage(&self, url: &str) -> Result<String> {
+    /// Returns [`None`] if the page redirected to an out-of-scope URL and
+    /// should be skipped.
+    fn get_webpage(&self, url: &str) -> Result<Option<String>> {
         trace!("getting webpage for '{url}'");
 
-        let req = self
-            .headers
-            .iter()
-            .fold(self.agent.get(url), |req, (key, value)| {
-                // trace!("Adding extra header {key}: {value}");
-                req.set(key, value)
-            });
-        let response = req
-            .call()
-            .map_err(|e| WalkFailedDiagnostic::new(url.to_string(), e))
-            .into_diagnostic()?;
+        let response = if self.check_redirect_scope {
+            let Some(response) = self.get_in_scope(url)? else {
+                return Ok(None);
+            };
+            response
+        } else {
+            self.get(url)?
+        };
 
-        // Check that we got HTML back
-        if let Some(content_type) = response.header("Content-Type") {
-            if !content_type.contains("html") {
+        // Check that we got HTML back. Generic or missing content types are
+        // checked by sniffing the body.
+        let content_type = response.header("Content-Type").map(str::to_string);
+        let needs_sniffing = is_generic_content_type(content_type.as_deref());
+        if let Some(content_type) = content_type.as_deref() {
+            if !needs_sniffing && !content_type.contains("html") {
                 return NotHtmlDiagnostic::new(url, content_type).into();
             }
         }
@@ -303,11 +470,70 @@ impl WebsiteWalker {
             }
         }
         let webpage = response.into_string().into_diagnostic()?;
+        // Chunked responses have no Content-Length, so check the body itself
+        if webpage.trim().is_empty() {
+            return NoContentDiagnostic::new(url).into();
+        }
+        if needs_sniffing && !sniff_html(&webpage) {
+            return NotHtmlDiagnostic::new(url, content_type.unwrap_or_default()).into();
+        }
+        // Soft 404s and bot challenges would otherwise be crawled for junk links
+        if let Some(reason) = sniff_soft_error_page(&webpage) {
+            return SoftErrorPageDiagnostic::new(url, reason).into();
+        }
         trace!("got webpage for '{url}'");
-        Ok(webpage)
+        Ok(Some(webpage))
     }
 
-    fn send_scripts(&self, scripts: Vec<Script>) {
+    fn get(&self, url: &str) -> Result<Response> {
+        let req = self
+            .headers
+            .iter()
+            .fold(self.agent.get(url), |req, (key, value)| {
+                // trace!("Adding extra header {key}: {value}");
+                req.set(key, value)
+            });
+        req.call()
+            .map_err(|e| WalkFailedDiagnostic::new(url.to_string(), e))
+            .into_diagnostic()
+    }
+
+    /// Like [`WebsiteWalker::get`], but follows redirects only to whitelisted
+    /// domains. The agent does not follow redirects when scope checking is
+    /// enabled.
+    fn get_in_scope(&self, url: &str) -> Result<Option<Response>> {
+        let mut current = url.to_string();
+        for _ in 0..=self.max_redirects {
+            let response = self.get(&current)?;
+            if !(300..400).contains(&response.status()) {
+                return Ok(Some(response));
+            }
+            let Some(location) = response.header("Location") else {
+                return Ok(Some(response));
+            };
+            let next = Url::parse(&current)
+                .and_then(|base| base.join(location))
+                .into_diagnostic()
+                .with_context(|| format!("{current} redirected to an invalid URL"))?;
+            if !self.is_whitelisted_link(&next) {
+                let skipped = Error::new(OutOfScopeRedirectDiagnostic::new(current, next.as_str()));
+                warn!("{skipped:?}");
+                return Ok(None);
+            }
+            trace!("({current}) following redirect to {next}");
+            current = next.into();
+        }
+
+        TooManyRedirectsDiagnostic::new(url, self.max_redirects).into()
+    }
+
+    fn send_scripts(
+        &self,
+        page_url: &Arc<Url>,
+        webpage: &str,
+        scripts: Vec<Script>,
+        integrity: &FxHashMap<Url, String>,
+    ) {
         let base_url = self.base_url.get().unwrap();
 
         let scripts = scripts
@@ -315,20 +541,41 @@ impl WebsiteWalker {
             // filter out scripts that have already been sent
             .filter_map(|script| match script {
                 Script::Url(script) => {
-                    if self.cache.has_seen_script(&script) {
+                    if self.cache.see_script(script.clone()) {
                         trace!("({script}) not sending script - already seen");
-                        None
-                    } else {
-                        self.cache.see_script(script.clone());
-                        Some(Script::Url(script))
+                        return None;
+                    }
+                    // the same script served from another URL
+                    if let Some(integrity) = integrity.get(script.as_ref()) {
+                        if self.cache.has_seen_integrity(integrity) {
+                            trace!("({script}) not sending script - integrity hash already seen");
+                            return None;
+                        }
+                        self.cache.see_integrity(integrity);
                     }
+                    Some(Script::Url(script))
                 }
                 embed => Some(embed),
             })
             .collect::<Vec<_>>();
         trace!("({}) Sending {} new scripts", base_url, scripts.len());
 
-        self.send(ScriptMessage::Scripts(scripts));
+        let page = scripts
+            .iter()
+            .any(|script| matches!(script, Script::Embedded(_, _, Some(_))))
+            .then(|| Arc::from(webpage));
+        self.send(ScriptMessage::Scripts {
+            referrer: Some(Arc::clone(page_url)),
+            scripts,
+            page,
+        });
+    }
+
+    fn send_page_error(&self, url: &Arc<Url>, error: Error) {
+        self.send(ScriptMessage::PageError {
+            url: Arc::clone(url),
+            error: Arc::new(error),
+        });
     }
 
     fn send(&self, message: ScriptMessage) {
@@ -344,6 +591,25 @@ impl WebsiteWalker {
             .is_some_and(|domain| self.is_allowed_domain(domain))
     }
 
+    /// Check a link's path against include and exclude patterns and the path
+    /// prefix. Excludes take precedence.
+    fn is_included_path(&self, link: &Url) -> bool {
+        let path = link.path();
+        if self.exclude_patterns.iter().any(|p| p.matches(path)) {
+            trace!("({link}) skipping excluded link");
+            return false;
+        }
+        if self
+            .path_prefix
+            .as_deref()
+            .is_some_and(|prefix| !path.starts_with(prefix))
+        {
+            trace!("({link}) skipping link outside of path prefix");
+            return false;
+        }
+        self.include_patterns.is_empty() || self.include_patterns.iter().any(|p| p.matches(path))
+    }
+
     fn is_allowed_domain(&self, domain: &str) -> bool {
         self.domain_whitelist.iter().any(|d| d.as_str() == domain)
     }
@@ -351,12 +617,10 @@ impl WebsiteWalker {
     fn finish(&self) {
         debug!("({}) finishing walk", self.base_url.get().unwrap());
 
-        if !self.close_channel_when_done {
-            return;
-        }
-
+        // always stop further page loads, even when the channel is shared
+        // with other walks
         let already_done = self.done.swap(true, Ordering::Relaxed);
-        if !already_done {
+        if !already_done && self.close_channel_when_done {
             let _ = self.sender.send(ScriptMessage::Done);
         }
     }
@@ -392,26 +656,572 @@ impl WebsiteWalker {
 
     #[inline]
     fn is_done(&self) -> bool {
-        self.done.load(Ordering::Relaxed)
+        self.done.load(Ordering::Relaxed) || self.cancel.is_cancelled()
     }
 }
 
 #[cfg(test)]
 mod test {
-    use crate::walk::website::WebsiteWalkBuilder;
-    use std::time::Duration;
+    use super::NoContentDiagnostic;
+    use crate::{
+        testing::{Response, TestServer},
+        walk::website::{WebsiteWalkBuilder, WebsiteWalker},
+        CancellationToken, Pattern, Script, ScriptMessage,
+    };
+    use regex::Regex;
+    use std::{
+        fmt::Write as _,
+        sync::{
+            atomic::{AtomicUsize, Ordering},
+            Arc,
+        },
+        time::{Duration, Instant},
+    };
+    use url::Url;
+
+    /// A stand-in for news.ycombinator.com: a front page linking to items and
+    /// other listings, all of which load the same script.
+    fn news_site() -> TestServer {
+        let page = |links: &[&str]| {
+            let links: String = links.iter().fold(String::new(), |mut html, link| {
+                let _ = write!(html, r#"<a href="{link}">{link}</a>"#);
+                html
+            });
+            Response::html(format!(
+                r#"<html><head><script type="text/javascript" src="hn.js?v=1"></script></head><body>{links}</body></html>"#
+            ))
+        };
+        TestServer::builder()
+            .route("/", page(&["newest", "item?id=1", "item?id=2", "login"]))
+            .route("/newest", page(&["item?id=3", "news?p=2"]))
+            .route("/news", page(&["item?id=1"]))
+            .route("/item", page(&["user?id=pg", "reply?id=1"]))
+            .route("/user", page(&[]))
+            .route("/login", page(&[]))
+            .route("/hn.js", Response::js("function vote(id, how) {}"))
+            .start()
+    }
 
     #[test]
     fn test_yc() {
-        const URL: &str = "https://news.ycombinator.com/";
+        let server = news_site();
         let scripts = WebsiteWalkBuilder::default()
             .with_random_ua(true)
             .with_max_walks(20)
             .with_timeout(Duration::from_secs(5))
             .with_timeout_connect(Duration::from_secs(2))
-            .collect(URL)
+            .collect(server.url())
             .unwrap();
 
-        assert!(!scripts.is_empty());
+        // every page loads hn.js, but it is only reported once
+        assert_eq!(scripts.len(), 1, "{scripts:?}");
+        assert!(matches!(&scripts[0], Script::Url(url) if url.path() == "/hn.js"));
+        assert!(!server.requests_for("/item").is_empty());
+        assert!(!server.requests_for("/user").is_empty());
+        // unknown pages are reported as errors, not fatal
+        assert!(!server.requests_for("/reply").is_empty());
+    }
+
+    fn redirecting_servers() -> (TestServer, TestServer) {
+        let off_domain = TestServer::builder()
+            .route(
+                "/",
+                Response::html(
+                    r#"<html><head><script src="/off-domain.js"></script></head></html>"#,
+                ),
+            )
+            .start();
+        // IP addresses never match the whitelisted `localhost` domain
+        let location = off_domain.url().replace("localhost", "127.0.0.1");
+        let server = TestServer::builder()
+            .route(
+                "/",
+                Response::html(r#"<html><body><a href="/go">Go</a></body></html>"#),
+            )
+            .route(
+                "/go",
+                Response::status(302).with_header("Location", location),
+            )
+            .start();
+
+        (server, off_domain)
+    }
+
+    #[test]
+    fn test_redirect_scope_check() {
+        let (server, off_domain) = redirecting_servers();
+        let scripts = WebsiteWalkBuilder::default()
+            .with_timeout(Duration::from_secs(1))
+            .with_redirect_scope_check(true)
+            .collect(server.url())
+            .unwrap();
+
+        assert_eq!(server.requests_for("/go").len(), 1);
+        assert!(off_domain.requests().is_empty());
+        assert!(scripts.is_empty());
+    }
+
+    #[test]
+    fn test_redirect_without_scope_check() {
+        let (server, off_domain) = redirecting_servers();
+        let scripts = WebsiteWalkBuilder::default()
+            .with_timeout(Duration::from_secs(1))
+            .collect(server.url())
+            .unwrap();
+
+        assert_eq!(off_domain.requests_for("/").len(), 1);
+        assert!(scripts
+            .iter()
+            .any(|script| matches!(script, Script::Url(url) if url.path() == "/off-domain.js")));
+    }
+
+    #[test]
+    fn test_max_redirects() {
+        let (server, off_domain) = redirecting_servers();
+        // walk fails since /go responds with a non-HTML redirect
+        let _ = WebsiteWalkBuilder::default()
+            .with_timeout(Duration::from_secs(1))
+            .with_max_redirects(0)
+            .collect(server.url());
+
+        assert_eq!(server.requests_for("/go").len(), 1);
+        assert!(off_domain.requests().is_empty());
+    }
+
+    #[test]
+    fn test_page_error() {
+        let server = TestServer::builder()
+            .route(
+                "/",
+                Response::html(
+                    r#"<html><body><a href="/missing">Missing</a><a href="/about">About</a></body></html>"#,
+                ),
+            )
+            .route("/about", Response::html("<html><body>About</body></html>"))
+            .start();
+        let (walker, rx) = WebsiteWalkBuilder::default()
+            .with_timeout(Duration::from_secs(1))
+            .build_with_channel();
+
+        walker.walk(&server.url()).unwrap();
+        let errors: Vec<_> = rx
+            .try_iter()
+            .filter_map(|message| match message {
+                ScriptMessage::PageError { url, error } => Some((url, error)),
+                _ => None,
+            })
+            .collect();
+
+        assert_eq!(errors.len(), 1);
+        let (url, error) = &errors[0];
+        assert_eq!(url.path(), "/missing");
+        assert!(error.to_string().contains("404"), "{error}");
+    }
+
+    #[test]
+    fn test_did_walk_page() {
+        let home = r#"<html><body><a href="/about">About</a></body></html>"#;
+        let server = TestServer::builder()
+            .route("/", Response::html(home))
+            .route("/about", Response::html("<html><body>About</body></html>"))
+            .start();
+        let (walker, rx) = WebsiteWalkBuilder::default()
+            .with_timeout(Duration::from_secs(1))
+            .build_with_channel();
+
+        walker.walk(&server.url()).unwrap();
+        let pages: Vec<_> = rx
+            .try_iter()
+            .filter_map(|message| match message {
+                ScriptMessage::DidWalkPage { url, bytes } => Some((url.path().to_string(), bytes)),
+                _ => None,
+            })
+            .collect();
+
+        assert_eq!(
+            pages,
+            vec![
+                ("/".to_string(), home.len()),
+                (
+                    "/about".to_string(),
+                    "<html><body>About</body></html>".len()
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_soft_error_page() {
+        let server = TestServer::builder()
+            .route(
+                "/",
+                Response::html(
+                    r#"<html><body><a href="/missing">Missing</a><a href="/about">About</a></body></html>"#,
+                ),
+            )
+            .route(
+                "/missing",
+                Response::html(
+                    r#"<html><head><title>Page Not Found</title></head><body><a href="/junk">Home</a></body></html>"#,
+                ),
+            )
+            .route("/about", Response::html("<html><body>About</body></html>"))
+            .start();
+        let (walker, rx) = WebsiteWalkBuilder::default()
+            .with_timeout(Duration::from_secs(1))
+            .build_with_channel();
+
+        walker.walk(&server.url()).unwrap();
+        let errors: Vec<_> = rx
+            .try_iter()
+            .filter_map(|message| match message {
+                ScriptMessage::PageError { url, error } => Some((url, error)),
+                _ => None,
+            })
+            .collect();
+
+        assert_eq!(errors.len(), 1);
+        let (url, error) = &errors[0];
+        assert_eq!(url.path(), "/missing");
+        assert!(error.to_string().contains("error page"), "{error}");
+        assert_eq!(server.requests_for("/about").len(), 1);
+        assert!(server.requests_for("/junk").is_empty());
+    }
+
+    #[test]
+    fn test_empty_chunked_page() {
+        let chunked = |body: &str| Response {
+            status: 200,
+            headers: vec![
+                ("Content-Type".into(), "text/html".into()),
+                ("Transfer-Encoding".into(), "chunked".into()),
+            ],
+            body: body.into(),
+        };
+        let server = TestServer::builder()
+            .route(
+                "/",
+                Response::html(
+                    r#"<html><body><a href="/empty">Empty</a><a href="/blank">Blank</a></body></html>"#,
+                ),
+            )
+            .route("/empty", chunked(""))
+            .route("/blank", chunked("\n  \n"))
+            .start();
+        let (walker, rx) = WebsiteWalkBuilder::default()
+            .with_timeout(Duration::from_secs(1))
+            .build_with_channel();
+
+        walker.walk(&server.url()).unwrap();
+        let mut errors: Vec<_> = rx
+            .try_iter()
+            .filter_map(|message| match message {
+                ScriptMessage::PageError { url, error } => Some((url, error)),
+                _ => None,
+            })
+            .collect();
+        errors.sort_by(|(a, _), (b, _)| a.path().cmp(b.path()));
+
+        assert_eq!(errors.len(), 2);
+        for ((url, error), path) in errors.iter().zip(["/blank", "/empty"]) {
+            assert_eq!(url.path(), path);
+            assert!(
+                error.downcast_ref::<NoContentDiagnostic>().is_some(),
+                "{error:?}"
+            );
+        }
+    }
+
+    #[test]
+    fn test_shared_channel() {
+        let servers: Vec<_> = (0..3)
+            .map(|i| {
+                TestServer::builder()
+                    .route(
+                        "/",
+                        Response::html(format!(
+                            r#"<html><head><script src="/main{i}.js"></script></head></html>"#
+                        )),
+                    )
+                    .start()
+            })
+            .collect();
+        let builder = WebsiteWalkBuilder::default()
+            .with_timeout(Duration::from_secs(1))
+            .with_close_channel(false);
+        let (tx, rx) = builder.channel();
+
+        // stand-in for ApiKeyCollector::collect
+        let receiver = std::thread::spawn(move || {
+            let mut scripts = vec![];
+            while let Ok(message) = rx.recv() {
+                match message {
+                    ScriptMessage::Done => break,
+                    ScriptMessage::Scripts { scripts: found, .. } => scripts.extend(found),
+                    _ => {}
+                }
+            }
+            scripts
+        });
+
+        for server in &servers {
+            builder.build(tx.clone()).walk(&server.url()).unwrap();
+        }
+        // `tx` is still alive, so only Done stops the receiver
+        WebsiteWalker::signal_done(&tx).unwrap();
+        let scripts = receiver.join().unwrap();
+
+        assert_eq!(scripts.len(), 3);
+        for (i, server) in servers.iter().enumerate() {
+            let expected =
+                Script::from(Url::parse(&server.url_for(&format!("/main{i}.js"))).unwrap());
+            assert!(scripts.contains(&expected), "missing {expected:?}");
+        }
+        // the receiver is gone, so signaling again fails
+        assert!(WebsiteWalker::signal_done(&tx).is_err());
+    }
+
+    #[test]
+    fn test_channel_backpressure() {
+        const PAGES: usize = 30;
+        const CAPACITY: usize = 2;
+
+        // each page links to the next one, so pages are walked one at a time
+        let served = Arc::new(AtomicUsize::new(0));
+        let builder = (0..PAGES).fold(TestServer::builder(), |builder, i| {
+            let served = Arc::clone(&served);
+            builder.route_fn(format!("/{i}"), move |_| {
+                served.fetch_add(1, Ordering::SeqCst);
+                let next = if i + 1 < PAGES {
+                    format!(r#"<a href="/{}">Next</a>"#, i + 1)
+                } else {
+                    String::new()
+                };
+                Response::html(format!(
+                    r#"<html><head><script src="/{i}.js"></script></head><body>{next}</body></html>"#
+                ))
+            })
+        });
+        let server = builder.start();
+        let (walker, rx) = WebsiteWalkBuilder::default()
+            .with_timeout(Duration::from_secs(1))
+            .with_unlimited_walks()
+            .with_channel_capacity(CAPACITY)
+            .build_with_channel();
+
+        let url = server.url_for("/0");
+        let handle = std::thread::spawn(move || walker.walk(&url));
+
+        // a slow consumer
+        let mut pages_received = 0;
+        let mut max_lag = 0;
+        while let Ok(message) = rx.recv() {
+            match message {
+                ScriptMessage::Done => break,
+                ScriptMessage::DidWalkPage { .. } => pages_received += 1,
+                _ => {}
+            }
+            max_lag = max_lag.max(served.load(Ordering::SeqCst) - pages_received);
+            std::thread::sleep(Duration::from_millis(10));
+        }
+        handle.join().unwrap().unwrap();
+
+        assert_eq!(pages_received, PAGES);
+        // each page sends two messages. Allow for one page being fetched while
+        // the walker is blocked on a send.
+        assert!(max_lag <= CAPACITY + 2, "walker got {max_lag} pages ahead");
+    }
+
+    /// A site with `/app` and `/blog` sections that link to each other.
+    fn sectioned_site() -> TestServer {
+        let page = |links: &[&str]| {
+            let links: String = links.iter().fold(String::new(), |mut html, link| {
+                let _ = write!(html, r#"<a href="{link}">{link}</a>"#);
+                html
+            });
+            Response::html(format!("<html><body>{links}</body></html>"))
+        };
+        TestServer::builder()
+            .route("/", page(&["/app/home", "/blog/post-1", "/about"]))
+            .route(
+                "/app/home",
+                page(&["/app/settings", "/app/private/keys", "/blog/post-2"]),
+            )
+            .route("/app/settings", page(&[]))
+            .route("/app/private/keys", page(&[]))
+            .route("/blog/post-1", page(&["/blog/post-2"]))
+            .route("/blog/post-2", page(&[]))
+            .route("/about", page(&[]))
+            .start()
+    }
+
+    fn crawled_paths(server: &TestServer) -> Vec<String> {
+        let mut paths: Vec<_> = server
+            .requests()
+            .into_iter()
+            .map(|request| request.path.clone())
+            .collect();
+        paths.sort_unstable();
+        paths.dedup();
+        paths
+    }
+
+    #[test]
+    fn test_exclude_pattern() {
+        let server = sectioned_site();
+        WebsiteWalkBuilder::default()
+            .with_timeout(Duration::from_secs(1))
+            .with_exclude_pattern(Pattern::glob("/blog/**"))
+            .collect(server.url())
+            .unwrap();
+
+        assert_eq!(
+            crawled_paths(&server),
+            [
+                "/",
+                "/about",
+                "/app/home",
+                "/app/private/keys",
+                "/app/settings"
+            ]
+        );
+    }
+
+    #[test]
+    fn test_include_pattern() {
+        let server = sectioned_site();
+        WebsiteWalkBuilder::default()
+            .with_timeout(Duration::from_secs(1))
+            .with_include_pattern(Pattern::glob("/app/**"))
+            // excludes take precedence
+            .with_exclude_pattern(Pattern::from(Regex::new("^/app/private").unwrap()))
+            .collect(server.url())
+            .unwrap();
+
+        // the entrypoint is always visited
+        assert_eq!(crawled_paths(&server), ["/", "/app/home", "/app/settings"]);
+    }
+
+    #[test]
+    fn test_same_path_prefix() {
+        let server = sectioned_site();
+        WebsiteWalkBuilder::default()
+            .with_timeout(Duration::from_secs(1))
+            .with_same_path_prefix("app/")
+            .collect(server.url())
+            .unwrap();
+
+        assert_eq!(
+            crawled_paths(&server),
+            ["/", "/app/home", "/app/private/keys", "/app/settings"]
+        );
+    }
+
+    #[test]
+    fn test_relative_urls_in_nested_page() {
+        let server = TestServer::builder()
+            .route(
+                "/a/b/",
+                Response::html(
+                    r#"<html><head><script src="../c/app.js"></script></head><body><a href="d">d</a></body></html>"#,
+                ),
+            )
+            .route(
+                "/a/b/d",
+                Response::html(r#"<html><head><script src="e.js"></script></head></html>"#),
+            )
+            .start();
+
+        let mut scripts = WebsiteWalkBuilder::default()
+            .with_timeout(Duration::from_secs(1))
+            .collect(server.url_for("/a/b/"))
+            .unwrap();
+        scripts.sort_unstable();
+
+        let expected: Vec<_> = ["/a/b/e.js", "/a/c/app.js"]
+            .into_iter()
+            .map(|path| Script::from(Url::parse(&server.url_for(path)).unwrap()))
+            .collect();
+        assert_eq!(scripts, expected);
+        assert_eq!(server.requests_for("/a/b/d").len(), 1);
+    }
+
+    #[test]
+    fn test_integrity_dedup() {
+        const SRI: &str = "sha384-oqVuAfXRKap7fdgcCY5uykM6+R9GqQ8K/uxy9rx7HNQlGYl1kPzQho1wx4JwY8wC";
+        let server = TestServer::builder()
+            .route(
+                "/",
+                Response::html(format!(
+                    r#"<html><head>
+<script src="https://cdn-a.example.com/lib.js" integrity="{SRI}"></script>
+<script src="/vendor/lib.js" integrity="sha512-other {SRI}"></script>
+<script src="/app.js" integrity="sha384-different"></script>
+</head><body><a href="/about">about</a></body></html>"#
+                )),
+            )
+            .route(
+                "/about",
+                Response::html(format!(
+                    r#"<html><head><script src="/mirror/lib.js" integrity="{SRI}"></script></head></html>"#
+                )),
+            )
+            .start();
+
+        let scripts = WebsiteWalkBuilder::default()
+            .with_timeout(Duration::from_secs(1))
+            .collect(server.url())
+            .unwrap();
+
+        let urls: Vec<_> = scripts
+            .iter()
+            .map(|script| match script {
+                Script::Url(url) => url.as_str().to_string(),
+                Script::Embedded(..) => unreachable!(),
+            })
+            .collect();
+        // the library is only sent once, no matter where it is loaded from
+        assert_eq!(
+            urls,
+            vec![
+                "https://cdn-a.example.com/lib.js".to_string(),
+                server.url_for("/app.js"),
+            ]
+        );
+        assert_eq!(server.requests_for("/about").len(), 1);
+    }
+
+    #[test]
+    fn test_cancel() {
+        const PAGES: usize = 20;
+        // each page is slow and links to the next one
+        let builder = (0..PAGES).fold(TestServer::builder(), |builder, i| {
+            builder.route_fn(format!("/{i}"), move |_| {
+                std::thread::sleep(Duration::from_millis(100));
+                Response::html(format!(
+                    r#"<html><body><a href="/{}">Next</a></body></html>"#,
+                    i + 1
+                ))
+            })
+        });
+        let server = builder.start();
+        let token = CancellationToken::new();
+        let (walker, rx) = WebsiteWalkBuilder::default()
+            .with_timeout(Duration::from_secs(1))
+            .with_cancel(token.clone())
+            .build_with_channel();
+
+        let start = Instant::now();
+        let url = server.url_for("/0");
+        let handle = std::thread::spawn(move || walker.walk(&url));
+        std::thread::sleep(Duration::from_millis(250));
+        token.cancel();
+        handle.join().unwrap().unwrap();
+
+        assert!(start.elapsed() < Duration::from_secs(1));
+        assert!(server.requests().len() < PAGES);
+        assert!(rx
+            .try_iter()
+            .any(|message| matches!(message, ScriptMessage::Done)));
     }
 }
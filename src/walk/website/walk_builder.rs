@@ -1,10 +1,24 @@
-use std::{borrow::Cow, num::NonZeroUsize, sync::mpsc, time::Duration};
+use std::{
+    borrow::Cow,
+    fs::File,
+    io::BufReader,
+    num::NonZeroUsize,
+    path::{Path, PathBuf},
+    sync::mpsc,
+    time::Duration,
+};
 
-use miette::{Error, MietteDiagnostic, Result};
+use cookie_store::CookieStore;
+use log::{debug, warn};
+use miette::{Error, IntoDiagnostic as _, MietteDiagnostic, Result};
+use rustc_hash::FxHashSet;
 use ureq::{Agent, AgentBuilder};
 
-use super::{walk::ScriptSender, walk_cache::WalkCache, Script};
-use crate::{http::random_ua, ScriptReceiver, WebsiteWalker};
+use super::{walk::ScriptSender, walk_cache::WalkCache, CacheStats, CancellationToken, Script};
+use crate::{
+    http::{insecure_tls_config, random_ua_for, Credentials, SeedCookie, UaProfile},
+    Pattern, ScriptReceiver, WebsiteWalker,
+};
 
 #[derive(Debug, Clone)]
 #[must_use]
@@ -20,6 +34,10 @@ pub struct WebsiteWalkBuilder {
     ///
     /// Default [`Some`] user agent
     pub(crate) ua: Option<Cow<'static, str>>,
+    /// Kind of device random user agents are picked for
+    ///
+    /// Default [`UaProfile::Random`]
+    ua_profile: UaProfile,
     /// Extra headers to add to requests
     ///
     /// By default, the following headers are added:
@@ -30,6 +48,10 @@ pub struct WebsiteWalkBuilder {
     /// - `Accept-Encoding: gzip, deflate, br`
     /// - `DNT: 1`
     pub(crate) headers: Vec<(String, String)>,
+    /// `Accept-Language` values to repeat the crawl with, one walk per locale.
+    ///
+    /// Default `[]` (crawl once with the `Accept-Language` header in `headers`)
+    locales: Vec<String>,
     /// Domains that can be visited (and have their scripts extracted)
     ///
     /// When a walk begins, the domain of the URL is checked against this list.
@@ -44,6 +66,41 @@ pub struct WebsiteWalkBuilder {
     ///
     /// Default `true`
     store_cookies: bool,
+    /// When set, cookies are loaded from this file when the walk starts and
+    /// saved to it when the walk ends. Only used when cookies are stored.
+    ///
+    /// Default [`None`]
+    pub(crate) cookie_store_path: Option<PathBuf>,
+    /// Cookies added to the cookie jar before the walk starts.
+    pub(crate) cookies: Vec<SeedCookie>,
+    /// Maximum number of redirects to follow for a single request.
+    ///
+    /// Default `5`
+    pub(crate) max_redirects: u32,
+    /// When `true`, each redirect's target is checked against the domain
+    /// whitelist before it is followed. Out-of-scope pages are skipped.
+    ///
+    /// Default `false`
+    pub(crate) check_redirect_scope: bool,
+    /// Only crawl pages whose path matches one of these patterns. When empty,
+    /// all paths are crawled.
+    pub(crate) include_patterns: Vec<Pattern>,
+    /// Never crawl pages whose path matches one of these patterns. Takes
+    /// precedence over `include_patterns`.
+    pub(crate) exclude_patterns: Vec<Pattern>,
+    /// Only crawl pages whose path starts with this prefix, e.g. `/app/`.
+    ///
+    /// Default [`None`]
+    pub(crate) path_prefix: Option<String>,
+    /// Stops the walk when cancelled.
+    ///
+    /// Default [`None`]
+    pub(crate) cancel: Option<CancellationToken>,
+    /// Maximum number of messages queued on the script channel before the
+    /// walker blocks.
+    ///
+    /// Default [`WebsiteWalkBuilder::DEFAULT_CHANNEL_CAPACITY`]
+    channel_capacity: usize,
     /// Shared cache across walks
     pub(crate) cache: Option<WalkCache>,
     /// Timeout for requests
@@ -58,6 +115,14 @@ pub struct WebsiteWalkBuilder {
     ///
     /// Default [`None`]
     pub(crate) timeout_connect: Option<Duration>,
+    /// When `false`, invalid TLS certificates are accepted.
+    ///
+    /// Default `true`
+    verify_tls: bool,
+    /// Preconfigured agent used instead of building one.
+    ///
+    /// Default [`None`]
+    agent: Option<Agent>,
 }
 
 impl Default for WebsiteWalkBuilder {
@@ -69,31 +134,48 @@ impl Default for WebsiteWalkBuilder {
             ),
             ("Keep-Alive".into(), "timeout=5, max=100".into()),
             ("Connection".into(), "keep-alive".into()),
-            ("Accept-Language".into(), "en-US,en;q=0.5".into()),
+            (Self::ACCEPT_LANGUAGE.into(), "en-US,en;q=0.5".into()),
             // TODO: use flat2 to decompress responses
             // ("Accept-Encoding".into(), "gzip, deflate, br".into()),
             ("DNT".into(), "1".into()),
         ];
 
         let mut rng = rand::thread_rng();
-        let ua = Some(Cow::Borrowed(random_ua(&mut rng)));
+        let ua = Some(Cow::Borrowed(random_ua_for(&mut rng, UaProfile::Random)));
 
         Self {
             max_walks: None,
             ua,
+            ua_profile: UaProfile::Random,
             headers,
+            locales: Vec::new(),
             domain_whitelist: Vec::new(),
             close_channel_when_done: true,
             timeout: None,
             timeout_connect: None,
             store_cookies: true,
+            cookie_store_path: None,
+            cookies: vec![],
+            max_redirects: Self::DEFAULT_MAX_REDIRECTS,
+            check_redirect_scope: false,
+            include_patterns: vec![],
+            exclude_patterns: vec![],
+            path_prefix: None,
+            cancel: None,
+            channel_capacity: Self::DEFAULT_CHANNEL_CAPACITY,
             cache: None,
+            verify_tls: true,
+            agent: None,
         }
     }
 }
 
 impl WebsiteWalkBuilder {
     const USER_AGENT: &'static str = "User-Agent";
+    const ACCEPT_LANGUAGE: &'static str = "Accept-Language";
+    const REFERER: &'static str = "Referer";
+    const DEFAULT_MAX_REDIRECTS: u32 = 5;
+    pub const DEFAULT_CHANNEL_CAPACITY: usize = 1024;
 
     /// Create a new builder with default settings
     pub fn new() -> Self {
@@ -146,7 +228,7 @@ impl WebsiteWalkBuilder {
     pub fn with_random_ua(mut self, yes: bool) -> Self {
         if yes && self.ua.is_none() {
             let mut rng = rand::thread_rng();
-            self.ua = Some(Cow::Borrowed(random_ua(&mut rng)));
+            self.ua = Some(Cow::Borrowed(random_ua_for(&mut rng, self.ua_profile)));
         } else if !yes {
             self.ua = None;
         }
@@ -154,6 +236,23 @@ impl WebsiteWalkBuilder {
         self
     }
 
+    /// Only use random `User-Agent`s for the given kind of device. This implies
+    /// [`WebsiteWalkBuilder::with_random_ua`].
+    ///
+    /// Random `User-Agent`s are replaced with one matching `profile`, but
+    /// `User-Agent`s set with [`WebsiteWalkBuilder::with_header`] are kept.
+    ///
+    /// Default [`UaProfile::Random`]
+    pub fn with_ua_profile(mut self, profile: UaProfile) -> Self {
+        self.ua_profile = profile;
+        // random UAs are borrowed from the UA pool, user-provided ones are owned
+        if !matches!(self.ua, Some(Cow::Owned(_))) {
+            let mut rng = rand::thread_rng();
+            self.ua = Some(Cow::Borrowed(random_ua_for(&mut rng, profile)));
+        }
+        self
+    }
+
     /// Add an extra header to all requests.
     ///
     /// Use [`WebsiteWalkBuilder::with_headers`] for adding multiple headers.
@@ -180,6 +279,112 @@ impl WebsiteWalkBuilder {
         self
     }
 
+    /// Set the `Accept-Language` header sent with all requests, replacing the
+    /// default `en-US,en;q=0.5`.
+    pub fn with_accept_language<S: Into<String>>(mut self, language: S) -> Self {
+        self.headers
+            .retain(|(k, _)| !k.eq_ignore_ascii_case(Self::ACCEPT_LANGUAGE));
+        self.headers
+            .push((Self::ACCEPT_LANGUAGE.into(), language.into()));
+        self
+    }
+
+    /// Send a `Referer` header with all page requests, e.g. for sites that
+    /// only serve visitors arriving from a search engine.
+    ///
+    /// Use [`ApiKeyCollector::with_referer`] or
+    /// [`ApiKeyCollector::with_page_referer`] to send a `Referer` when
+    /// downloading scripts.
+    ///
+    /// [`ApiKeyCollector::with_referer`]: crate::ApiKeyCollector::with_referer
+    /// [`ApiKeyCollector::with_page_referer`]: crate::ApiKeyCollector::with_page_referer
+    pub fn with_referer<S: Into<String>>(mut self, referer: S) -> Self {
+        self.headers
+            .retain(|(k, _)| !k.eq_ignore_ascii_case(Self::REFERER));
+        self.headers.push((Self::REFERER.into(), referer.into()));
+        self
+    }
+
+    /// Log in to pages with HTTP basic auth, e.g. for crawling a staging
+    /// site.
+    ///
+    /// Credentials are sent with every page request. Only pages on
+    /// whitelisted domains are visited, and credentials are not forwarded
+    /// when following redirects. Use [`ApiKeyCollector::with_basic_auth`] to
+    /// download scripts with the same credentials.
+    ///
+    /// [`ApiKeyCollector::with_basic_auth`]: crate::ApiKeyCollector::with_basic_auth
+    pub fn with_basic_auth<U: AsRef<str>, P: AsRef<str>>(self, user: U, password: P) -> Self {
+        self.with_credentials(Credentials::basic(user, password))
+    }
+
+    /// Send an `Authorization: Bearer` token with page requests, e.g. for
+    /// single-page apps whose config endpoints need a token.
+    ///
+    /// Like [`with_basic_auth`], the token is only sent to whitelisted domains.
+    /// Use [`ApiKeyCollector::with_bearer_token`] to download scripts with the
+    /// same token.
+    ///
+    /// [`with_basic_auth`]: WebsiteWalkBuilder::with_basic_auth
+    /// [`ApiKeyCollector::with_bearer_token`]: crate::ApiKeyCollector::with_bearer_token
+    pub fn with_bearer_token<T: AsRef<str>>(self, token: T) -> Self {
+        self.with_credentials(Credentials::bearer(token))
+    }
+
+    /// Replaces any existing `Authorization` header.
+    fn with_credentials(mut self, credentials: Credentials) -> Self {
+        self.headers
+            .retain(|(k, _)| !k.eq_ignore_ascii_case(Credentials::HEADER));
+        self.headers
+            .push((Credentials::HEADER.into(), credentials.header().into()));
+        self
+    }
+
+    /// Crawl sites once per locale, sending each locale as the
+    /// `Accept-Language` header. Sites that localize their pages may serve
+    /// different scripts to different languages.
+    ///
+    /// Each locale's walk has its own page cache so that pages are revisited
+    /// in every language, but scripts already found in another locale are not
+    /// sent again. Use [`WebsiteWalkBuilder::locale_builders`] to get one
+    /// builder per locale.
+    pub fn with_locales<I, S>(mut self, locales: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        self.locales = locales.into_iter().map(Into::into).collect();
+        self
+    }
+
+    /// Locales sites are crawled in. Empty when sites are only crawled once.
+    pub fn locales(&self) -> &[String] {
+        &self.locales
+    }
+
+    /// One builder per configured locale, each sending that locale as its
+    /// `Accept-Language` header. Their walks share seen scripts, so scripts
+    /// served to several locales are only sent once. When no locales are
+    /// configured, this is just a copy of this builder.
+    pub fn locale_builders(&self) -> Vec<WebsiteWalkBuilder> {
+        if self.locales.is_empty() {
+            return vec![self.clone()];
+        }
+
+        let cache = self.cache.clone().unwrap_or_default();
+        self.locales
+            .iter()
+            .map(|locale| {
+                let mut builder = self.clone().with_accept_language(locale.as_str());
+                builder.locales.clear();
+                // a shared page cache would skip pages already seen in another
+                // locale
+                builder.cache = Some(cache.with_fresh_urls());
+                builder
+            })
+            .collect()
+    }
+
     /// Whitelist a domain for crawling. Only domains in this list will have
     /// their pages scanned for scripts.
     ///
@@ -212,8 +417,47 @@ impl WebsiteWalkBuilder {
         self
     }
 
+    /// Only crawl pages whose URL path matches `pattern`, e.g.
+    /// `Pattern::glob("/app/**")`. Call this multiple times to crawl pages
+    /// matching any of several patterns.
+    ///
+    /// Only links found while crawling are filtered; the entrypoint is always
+    /// visited. Globs must match the entire path, while regexes and plain
+    /// strings may match anywhere in it.
+    pub fn with_include_pattern<P: Into<Pattern>>(mut self, pattern: P) -> Self {
+        self.include_patterns.push(pattern.into());
+        self
+    }
+
+    /// Never crawl pages whose URL path matches `pattern`, e.g.
+    /// `Pattern::glob("/blog/**")`. Excluded pages are skipped even if they
+    /// match an include pattern.
+    ///
+    /// See [`WebsiteWalkBuilder::with_include_pattern`].
+    pub fn with_exclude_pattern<P: Into<Pattern>>(mut self, pattern: P) -> Self {
+        self.exclude_patterns.push(pattern.into());
+        self
+    }
+
+    /// Only crawl pages whose URL path starts with `prefix`, e.g. `/app/` to
+    /// stay within one app on a larger site. Applies in addition to include
+    /// and exclude patterns.
+    ///
+    /// Like include patterns, only links found while crawling are filtered;
+    /// the entrypoint is always visited.
+    pub fn with_same_path_prefix<S: Into<String>>(mut self, prefix: S) -> Self {
+        let prefix = prefix.into();
+        self.path_prefix = Some(if prefix.starts_with('/') {
+            prefix
+        } else {
+            format!("/{prefix}")
+        });
+        self
+    }
+
     /// Close the script channel when the walk is done. If you plan on
-    /// performing multiple walks, leave the channel open.
+    /// performing multiple walks, leave the channel open and call
+    /// [`WebsiteWalker::signal_done`] after the last one.
     ///
     /// By default, the script channel will be closed when the walk is done.
     pub fn with_close_channel(mut self, yes: bool) -> Self {
@@ -221,6 +465,22 @@ impl WebsiteWalkBuilder {
         self
     }
 
+    /// Queue at most `capacity` messages on channels created by
+    /// [`WebsiteWalkBuilder::channel`] and [`WebsiteWalkBuilder::build_with_channel`].
+    ///
+    /// Once the queue is full, walkers block until their consumer (e.g. an
+    /// [`ApiKeyCollector`]) catches up, which bounds memory use on large
+    /// sites. Consume messages on a different thread than the one walking,
+    /// otherwise walks producing more than `capacity` messages never finish.
+    ///
+    /// Default [`WebsiteWalkBuilder::DEFAULT_CHANNEL_CAPACITY`]
+    ///
+    /// [`ApiKeyCollector`]: crate::ApiKeyCollector
+    pub fn with_channel_capacity(mut self, capacity: usize) -> Self {
+        self.channel_capacity = capacity;
+        self
+    }
+
     /// Store cookies and use them across requests.
     ///
     /// This is enabled by default.
@@ -229,6 +489,72 @@ impl WebsiteWalkBuilder {
         self
     }
 
+    /// Persist cookies to a file across runs.
+    ///
+    /// Cookies are loaded from `path` (if it exists) when the walker is built
+    /// and saved back to it once the walk is done. Useful for sites that sit
+    /// behind logins or bot challenges. This implies
+    /// [`WebsiteWalkBuilder::with_cookie_jar`].
+    ///
+    /// By default, cookies are only kept in memory.
+    pub fn with_cookie_store_path<P: Into<PathBuf>>(mut self, path: P) -> Self {
+        self.store_cookies = true;
+        self.cookie_store_path = Some(path.into());
+        self
+    }
+
+    /// Send a cookie with all requests to `domain` and its subdomains.
+    ///
+    /// Useful for crawling pages behind a login, e.g. by injecting a session
+    /// cookie. Seeded cookies override cookies loaded with
+    /// [`WebsiteWalkBuilder::with_cookie_store_path`]. This implies
+    /// [`WebsiteWalkBuilder::with_cookie_jar`].
+    pub fn with_cookie<N, V, D>(mut self, name: N, value: V, domain: D) -> Self
+    where
+        N: Into<String>,
+        V: Into<String>,
+        D: Into<String>,
+    {
+        self.store_cookies = true;
+        self.cookies.push(SeedCookie::new(name, value, domain));
+        if self.agent.is_some() {
+            warn!("Cookies are not added to agents set with WebsiteWalkBuilder::with_agent");
+        }
+        self
+    }
+
+    /// Follow at most `max_redirects` redirects per request. Set to `0` to
+    /// never follow redirects.
+    ///
+    /// Default `5`
+    pub fn with_max_redirects(mut self, max_redirects: u32) -> Self {
+        self.max_redirects = max_redirects;
+        self
+    }
+
+    /// Check that redirects stay on whitelisted domains.
+    ///
+    /// Redirects can silently take the crawler off of the site being scanned.
+    /// When enabled, each redirect's target is checked against the domain
+    /// whitelist before it is followed, and pages that redirect out of scope
+    /// are skipped.
+    ///
+    /// Default `false`
+    pub fn with_redirect_scope_check(mut self, yes: bool) -> Self {
+        self.check_redirect_scope = yes;
+        self
+    }
+
+    /// Stop walks when `token` is cancelled.
+    ///
+    /// Pages currently being fetched are allowed to finish, but no new pages
+    /// are visited. The script channel is closed as usual (see
+    /// [`WebsiteWalkBuilder::with_close_channel`]).
+    pub fn with_cancel(mut self, token: CancellationToken) -> Self {
+        self.cancel = Some(token);
+        self
+    }
+
     /// Share a URL and script cache across walks.
     ///
     /// Useful for avoiding duplicate work when performing multiple walks.
@@ -243,6 +569,13 @@ impl WebsiteWalkBuilder {
         self
     }
 
+    /// How often the shared cache prevented redundant work, including walks
+    /// by [`locale_builders`](WebsiteWalkBuilder::locale_builders). [`None`]
+    /// unless the cache is shared (see [`WebsiteWalkBuilder::with_shared_cache`]).
+    pub fn cache_stats(&self) -> Option<CacheStats> {
+        self.cache.as_ref().map(WalkCache::stats)
+    }
+
     pub fn clear_cache(&mut self) {
         self.cache.as_mut().map(WalkCache::clear);
     }
@@ -264,14 +597,69 @@ impl WebsiteWalkBuilder {
         self
     }
 
+    /// Check that sites' TLS certificates are valid. Disable verification to
+    /// crawl staging sites with self-signed certificates.
+    ///
+    /// Without verification, connections can be intercepted by anyone
+    /// between you and the site, so a warning is logged. Use
+    /// [`ApiKeyCollector::with_tls_verification`] to download scripts from
+    /// the same sites.
+    ///
+    /// Default `true`
+    ///
+    /// [`ApiKeyCollector::with_tls_verification`]: crate::ApiKeyCollector::with_tls_verification
+    pub fn with_tls_verification(mut self, yes: bool) -> Self {
+        self.verify_tls = yes;
+        self
+    }
+
+    /// Make requests with a preconfigured [`Agent`], e.g. one with TLS client
+    /// certificates, a custom DNS resolver, or a connection pool shared with
+    /// an [`ApiKeyCollector`].
+    ///
+    /// The agent is used as-is, so cookie, timeout, redirect, and TLS
+    /// settings on this builder are ignored, whether they were set before or
+    /// after it; configure them on the agent instead. A warning is logged if
+    /// cookies were seeded with [`WebsiteWalkBuilder::with_cookie`]. Redirect
+    /// scope checks (see [`WebsiteWalkBuilder::with_redirect_scope_check`])
+    /// need an agent that does not follow redirects.
+    ///
+    /// [`ApiKeyCollector`]: crate::ApiKeyCollector
+    pub fn with_agent(mut self, agent: Agent) -> Self {
+        if !self.cookies.is_empty() {
+            warn!("Cookies are not added to agents set with WebsiteWalkBuilder::with_agent");
+        }
+        self.agent = Some(agent);
+        self
+    }
+
     pub(crate) fn build_agent(&self) -> Agent {
+        if let Some(agent) = &self.agent {
+            return agent.clone();
+        }
+
         let mut builder = AgentBuilder::new();
 
         // enable/disable cookie jar
         if self.store_cookies {
-            builder = builder.cookie_store(Default::default());
+            let mut cookie_store = self
+                .cookie_store_path
+                .as_deref()
+                .map(Self::load_cookie_store)
+                .unwrap_or_default();
+            for cookie in &self.cookies {
+                cookie.insert_into(&mut cookie_store);
+            }
+            builder = builder.cookie_store(cookie_store);
         }
 
+        // redirects are followed by the walker when checking their scope
+        builder = builder.redirects(if self.check_redirect_scope {
+            0
+        } else {
+            self.max_redirects
+        });
+
         // set default timeout
         if let Some(timeout) = self.timeout {
             builder = builder.timeout(timeout);
@@ -282,9 +670,36 @@ impl WebsiteWalkBuilder {
             builder = builder.timeout_connect(connect_timeout);
         }
 
+        if !self.verify_tls {
+            builder = builder.tls_config(insecure_tls_config());
+        }
+
         builder.build()
     }
 
+    fn load_cookie_store(path: &Path) -> CookieStore {
+        if !path.exists() {
+            return CookieStore::default();
+        }
+
+        let loaded = File::open(path).into_diagnostic().and_then(|file| {
+            CookieStore::load_json_all(BufReader::new(file)).map_err(|e| Error::msg(e.to_string()))
+        });
+        match loaded {
+            Ok(store) => {
+                debug!("Loaded cookies from {}", path.display());
+                store
+            }
+            Err(e) => {
+                warn!(
+                    "{:?}",
+                    e.context(format!("Failed to load cookies from {}", path.display()))
+                );
+                CookieStore::default()
+            }
+        }
+    }
+
     pub(crate) fn headers(&self) -> impl Iterator<Item = (&str, &str)> + '_ {
         self.ua
             .as_ref()
@@ -297,13 +712,41 @@ impl WebsiteWalkBuilder {
         WebsiteWalker::new(self, sender)
     }
 
+    /// Create a script channel bounded by this builder's channel capacity.
+    ///
+    /// See [`WebsiteWalkBuilder::with_channel_capacity`].
+    pub fn channel(&self) -> (ScriptSender, ScriptReceiver) {
+        mpsc::sync_channel(self.channel_capacity)
+    }
+
     pub fn build_with_channel(&self) -> (WebsiteWalker, ScriptReceiver) {
-        let (tx, rx) = mpsc::channel();
+        let (tx, rx) = self.channel();
         let walker = WebsiteWalker::new(self, tx);
         (walker, rx)
     }
 
+    /// Crawl a site and collect every script found on it.
+    ///
+    /// With [`WebsiteWalkBuilder::with_locales`], the site is crawled once per
+    /// locale and scripts found in more than one locale are only returned once.
     pub fn collect<S: AsRef<str>>(&self, entrypoint: S) -> Result<Vec<Script>> {
+        if self.locales.is_empty() {
+            return self.collect_once(entrypoint.as_ref());
+        }
+
+        let mut seen = FxHashSet::default();
+        let mut scripts = Vec::new();
+        for builder in self.locale_builders() {
+            for script in builder.collect_once(entrypoint.as_ref())? {
+                if seen.insert(script.clone()) {
+                    scripts.push(script);
+                }
+            }
+        }
+        Ok(scripts)
+    }
+
+    fn collect_once<S: AsRef<str>>(&self, entrypoint: S) -> Result<Vec<Script>> {
         const ACC_INITIAL_CAPACITY: usize = 32;
 
         let (walker, receiver) = self.build_with_channel();
@@ -342,13 +785,14 @@ impl WebsiteWalkBuilder {
 mod test {
 
     use super::*;
+    use crate::testing::{Response, TestServer};
     #[test]
     fn test_builder() {
         let builder = WebsiteWalkBuilder::default()
             .with_max_walks(20)
             .with_shared_cache(true)
             .with_cookie_jar(true);
-        let (sender, _receiver) = mpsc::channel();
+        let (sender, _receiver) = builder.channel();
 
         let _walker: WebsiteWalker = builder.build(sender);
     }
@@ -403,4 +847,230 @@ mod test {
             "with_random_ua(true) should add a user agent"
         );
     }
+
+    #[test]
+    fn test_ua_profile() {
+        let builder = WebsiteWalkBuilder::default().with_ua_profile(UaProfile::Mobile);
+        let ua = builder.ua.as_deref().unwrap();
+        assert!(ua.contains("Mobile"), "{ua} is not a mobile user agent");
+
+        // profiles apply to random UAs added later
+        let builder = builder.with_random_ua(false).with_random_ua(true);
+        assert!(builder.ua.as_deref().unwrap().contains("Mobile"));
+
+        let builder = builder.with_ua_profile(UaProfile::Desktop);
+        assert!(!builder.ua.as_deref().unwrap().contains("Mobile"));
+
+        // user-provided UAs take precedence
+        let builder = WebsiteWalkBuilder::default()
+            .with_header("User-Agent", "my-crawler")
+            .with_ua_profile(UaProfile::Mobile);
+        assert_eq!(builder.ua.as_deref(), Some("my-crawler"));
+    }
+
+    #[test]
+    fn test_collect() {
+        let server = TestServer::builder()
+            .route(
+                "/",
+                Response::html(
+                    r#"
+<html>
+<head>
+    <script src="/main.js"></script>
+    <script src="/vendor.js"></script>
+    <script>console.log("inline")</script>
+</head>
+<body><a href="/about">about</a></body>
+</html>"#,
+                ),
+            )
+            .route(
+                "/about",
+                Response::html(
+                    r#"
+<html>
+<head><script src="/main.js"></script></head>
+<body><a href="/">home</a></body>
+</html>"#,
+                ),
+            )
+            .start();
+
+        let mut scripts = WebsiteWalkBuilder::default()
+            .with_timeout(Duration::from_secs(1))
+            .collect(server.url())
+            .unwrap();
+        scripts.sort_unstable();
+
+        let (urls, embedded): (Vec<_>, Vec<_>) = scripts
+            .iter()
+            .partition(|script| matches!(script, Script::Url(_)));
+        assert_eq!(
+            urls,
+            vec![
+                &Script::from(url::Url::parse(&server.url_for("/main.js")).unwrap()),
+                &Script::from(url::Url::parse(&server.url_for("/vendor.js")).unwrap()),
+            ]
+        );
+        assert_eq!(embedded.len(), 1);
+        // scripts are never downloaded
+        assert!(server.requests_for("/main.js").is_empty());
+        assert_eq!(server.requests_for("/about").len(), 1);
+    }
+
+    #[test]
+    fn test_accept_language() {
+        let server = TestServer::builder()
+            .route("/", Response::html("<html><body>bonjour</body></html>"))
+            .start();
+        let builder = WebsiteWalkBuilder::default()
+            .with_timeout(Duration::from_secs(1))
+            .with_accept_language("fr-FR,fr;q=0.8");
+        // the default header is replaced, not duplicated
+        let languages: Vec<_> = builder
+            .headers()
+            .filter(|(name, _)| name.eq_ignore_ascii_case(WebsiteWalkBuilder::ACCEPT_LANGUAGE))
+            .map(|(_, value)| value)
+            .collect();
+        assert_eq!(languages, vec!["fr-FR,fr;q=0.8"]);
+
+        builder.collect(server.url()).unwrap();
+        let requests = server.requests_for("/");
+        assert_eq!(requests.len(), 1);
+        assert_eq!(
+            requests[0].header("Accept-Language"),
+            Some("fr-FR,fr;q=0.8")
+        );
+    }
+
+    #[test]
+    fn test_locales() {
+        let server = TestServer::builder()
+            .route_fn("/", |req| {
+                let script = match req.header("Accept-Language") {
+                    Some("de") => "/de.js",
+                    _ => "/en.js",
+                };
+                Response::html(format!(
+                    r#"<html><head><script src="{script}"></script><script src="/main.js"></script></head></html>"#
+                ))
+            })
+            .start();
+
+        let mut scripts = WebsiteWalkBuilder::default()
+            .with_timeout(Duration::from_secs(1))
+            .with_shared_cache(true)
+            .with_locales(["en-US", "de"])
+            .collect(server.url())
+            .unwrap();
+        scripts.sort_unstable();
+
+        let requests = server.requests_for("/");
+        let languages: Vec<_> = requests
+            .iter()
+            .map(|req| req.header("Accept-Language"))
+            .collect();
+        assert_eq!(languages, vec![Some("en-US"), Some("de")]);
+        // scripts found in both locales are only returned once
+        let expected: Vec<_> = ["/de.js", "/en.js", "/main.js"]
+            .into_iter()
+            .map(|path| Script::from(url::Url::parse(&server.url_for(path)).unwrap()))
+            .collect();
+        assert_eq!(scripts, expected);
+
+        // walks over a shared channel don't send scripts seen in another locale
+        let builders = WebsiteWalkBuilder::default()
+            .with_timeout(Duration::from_secs(1))
+            .with_locales(["en-US", "de"])
+            .locale_builders();
+        let (tx_scripts, rx_scripts) = builders[0].channel();
+        for builder in builders {
+            builder
+                .with_close_channel(false)
+                .build(tx_scripts.clone())
+                .walk(&server.url())
+                .unwrap();
+        }
+        drop(tx_scripts);
+        let mut sent: Vec<_> = rx_scripts
+            .into_iter()
+            .filter_map(|message| match message {
+                crate::ScriptMessage::Scripts { scripts, .. } => Some(scripts),
+                _ => None,
+            })
+            .flatten()
+            .collect();
+        sent.sort_unstable();
+        assert_eq!(sent, expected);
+    }
+
+    #[test]
+    fn test_cache_stats() {
+        let page = |link: &str| {
+            Response::html(format!(
+                r#"<html><head><script src="/common.js"></script></head><body><a href="{link}">link</a></body></html>"#
+            ))
+        };
+        // two sites that link to the same page and load the same script
+        let server = TestServer::builder()
+            .route("/a", page("/shared"))
+            .route("/b", page("/shared"))
+            .route("/shared", page("/a"))
+            .start();
+
+        let builder = WebsiteWalkBuilder::default()
+            .with_timeout(Duration::from_secs(1))
+            .with_shared_cache(true);
+        assert_eq!(builder.cache_stats(), Some(CacheStats::default()));
+        builder.collect(server.url_for("/a")).unwrap();
+        let first = builder.cache_stats().unwrap();
+        builder.collect(server.url_for("/b")).unwrap();
+        let second = builder.cache_stats().unwrap();
+
+        assert_eq!(server.requests_for("/shared").len(), 1);
+        assert!(second.url_hits > first.url_hits, "{first:?} {second:?}");
+        assert!(
+            second.script_hits > first.script_hits,
+            "{first:?} {second:?}"
+        );
+        assert!(second.url_hit_rate() > 0.0);
+
+        // caches that aren't shared have no stats to report
+        assert_eq!(WebsiteWalkBuilder::default().cache_stats(), None);
+    }
+
+    #[test]
+    fn test_cookie_store_path() {
+        let server = TestServer::builder()
+            .route_fn("/", |req| {
+                let page = Response::html("<html><body>hello</body></html>");
+                if req.header("Cookie").is_some() {
+                    page
+                } else {
+                    page.with_header("Set-Cookie", "session=abc123; Path=/")
+                }
+            })
+            .start();
+        let cookie_path =
+            std::env::temp_dir().join(format!("keyhunter-cookies-{}.json", rand::random::<u32>()));
+        let builder = || {
+            WebsiteWalkBuilder::default()
+                .with_timeout(Duration::from_secs(1))
+                .with_cookie_store_path(&cookie_path)
+        };
+
+        // first walk receives the cookie and saves it
+        builder().collect(server.url()).unwrap();
+        assert!(cookie_path.is_file());
+        assert!(server.requests_for("/")[0].header("Cookie").is_none());
+
+        // second walk uses a new agent, but loads cookies from disk
+        builder().collect(server.url()).unwrap();
+        let requests = server.requests_for("/");
+        assert_eq!(requests.len(), 2);
+        assert_eq!(requests[1].header("Cookie"), Some("session=abc123"));
+
+        std::fs::remove_file(&cookie_path).unwrap();
+    }
 }
@@ -3,11 +3,66 @@ use rustc_hash::FxBuildHasher;
 use std::{
     borrow::{Borrow, Cow},
     hash::Hash,
-    ops::Deref,
-    sync::Arc,
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc,
+    },
 };
 use url::Url;
 
+/// How often a walk cache prevented pages from being revisited and scripts
+/// from being sent again. See [`WebsiteWalkBuilder::cache_stats`].
+///
+/// [`WebsiteWalkBuilder::cache_stats`]: crate::WebsiteWalkBuilder::cache_stats
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct CacheStats {
+    /// Pages skipped because they were already visited
+    pub url_hits: usize,
+    /// Pages visited for the first time
+    pub url_misses: usize,
+    /// Scripts skipped because they were already sent
+    pub script_hits: usize,
+    /// Scripts sent for the first time
+    pub script_misses: usize,
+}
+
+impl CacheStats {
+    /// Fraction of page lookups that were already visited, from `0.0` to
+    /// `1.0`. `0.0` when no pages were looked up.
+    pub fn url_hit_rate(&self) -> f64 {
+        hit_rate(self.url_hits, self.url_misses)
+    }
+
+    /// Fraction of script lookups that were already sent, from `0.0` to
+    /// `1.0`. `0.0` when no scripts were looked up.
+    pub fn script_hit_rate(&self) -> f64 {
+        hit_rate(self.script_hits, self.script_misses)
+    }
+}
+
+fn hit_rate(hits: usize, misses: usize) -> f64 {
+    match hits + misses {
+        0 => 0.0,
+        total => hits as f64 / total as f64,
+    }
+}
+
+#[derive(Debug, Default)]
+struct CacheCounters {
+    url_hits: AtomicUsize,
+    url_misses: AtomicUsize,
+    script_hits: AtomicUsize,
+    script_misses: AtomicUsize,
+}
+
+impl CacheCounters {
+    fn record(hits: &AtomicUsize, misses: &AtomicUsize, hit: bool) -> bool {
+        let counter = if hit { hits } else { misses };
+        counter.fetch_add(1, Ordering::Relaxed);
+        hit
+    }
+}
+
 #[derive(Debug, Default, Clone)]
 pub struct WalkCache {
     /// Web pages already visited. Prevents cycles.
@@ -15,11 +70,46 @@ pub struct WalkCache {
     /// Scripts already seen. Prevents duplicates from being sent over the
     /// script channel.
     seen_scripts: Arc<DashSet<Arc<Url>, FxBuildHasher>>,
+    /// Subresource integrity hashes of scripts already seen. The same script
+    /// served from different URLs (e.g. two CDNs) has the same hash.
+    seen_integrity: Arc<DashSet<Box<str>, FxBuildHasher>>,
+    /// Shared by caches created with [`WalkCache::with_fresh_urls`]
+    counters: Arc<CacheCounters>,
 }
 
 impl WalkCache {
-    pub fn see_script(&self, url: Arc<Url>) {
-        self.seen_scripts.insert(url);
+    /// A cache that has not visited any pages, but shares this cache's seen
+    /// scripts and [`stats`](WalkCache::stats). Walks using it revisit pages
+    /// without sending scripts that were already sent again.
+    pub fn with_fresh_urls(&self) -> Self {
+        Self {
+            seen_urls: Arc::default(),
+            seen_scripts: Arc::clone(&self.seen_scripts),
+            seen_integrity: Arc::clone(&self.seen_integrity),
+            counters: Arc::clone(&self.counters),
+        }
+    }
+
+    /// Hits and misses since the cache was created or last cleared.
+    pub fn stats(&self) -> CacheStats {
+        let counters = &self.counters;
+        CacheStats {
+            url_hits: counters.url_hits.load(Ordering::Relaxed),
+            url_misses: counters.url_misses.load(Ordering::Relaxed),
+            script_hits: counters.script_hits.load(Ordering::Relaxed),
+            script_misses: counters.script_misses.load(Ordering::Relaxed),
+        }
+    }
+
+    /// Remember a script, returning `true` if it had already been seen.
+    /// Counted as a hit or miss in [`stats`](WalkCache::stats).
+    pub fn see_script(&self, url: Arc<Url>) -> bool {
+        let counters = &self.counters;
+        CacheCounters::record(
+            &counters.script_hits,
+            &counters.script_misses,
+            !self.seen_scripts.insert(url),
+        )
     }
 
     pub fn has_seen_script<Q>(&self, url: &Q) -> bool
@@ -30,6 +120,22 @@ impl WalkCache {
         self.seen_scripts.contains(url)
     }
 
+    /// Remember a script's `integrity` attribute. Attributes may list several
+    /// hashes separated by whitespace, e.g. `sha384-... sha512-...`.
+    pub fn see_integrity(&self, integrity: &str) {
+        for hash in integrity.split_whitespace() {
+            self.seen_integrity.insert(hash.into());
+        }
+    }
+
+    /// Returns `true` if any hash in a script's `integrity` attribute belongs
+    /// to a script that has already been seen.
+    pub fn has_seen_integrity(&self, integrity: &str) -> bool {
+        integrity
+            .split_whitespace()
+            .any(|hash| self.seen_integrity.contains(hash))
+    }
+
     pub fn see_url(&self, url: Arc<Url>) {
         self.seen_urls.insert(url);
     }
@@ -49,31 +155,48 @@ impl WalkCache {
             "skip_if_visited got a relative url"
         ); // should be absolute
 
-        if url.query().is_none() && url.fragment().is_none() {
-            return self.has_visited_url_clean(Arc::clone(url));
+        self.has_visited_url_clean(Arc::new(Self::canonicalize(url)))
+    }
+
+    /// Normalize a URL so that URLs pointing to the same page compare equal.
+    ///
+    /// - hosts are lowercased and default ports are removed (`url` already
+    ///   does this for `http(s)` URLs when parsing)
+    /// - trailing slashes are removed from paths
+    /// - percent-encoded unreserved characters are decoded, and remaining
+    ///   escapes are uppercased
+    /// - `#section` hashes are removed
+    /// - (most) query parameters are removed, and the rest are sorted
+    pub fn canonicalize(url: &Url) -> Url {
+        let mut canonical = url.clone();
+        canonical.set_fragment(None);
+        canonical.set_query(None);
+
+        if let Some(host) = url.host_str() {
+            if host.bytes().any(|b| b.is_ascii_uppercase()) {
+                let _ = canonical.set_host(Some(&host.to_ascii_lowercase()));
+            }
+        }
+        if canonical.port().is_some() && canonical.port() == default_port(canonical.scheme()) {
+            let _ = canonical.set_port(None);
         }
 
-        // remove #section hash and (most) query parameters from URL since they
-        // don't affect what page the URL points to. Note that some applications
-        // use query parameters to identify what page to go to, thus the below
-        // query_pairs() check. We may need to update this list as new cases are
-        // brought to light.
-        let mut without_query_params = url.deref().clone();
-        without_query_params.set_query(None);
-        without_query_params.set_fragment(None);
-        let new_params = Self::filter_query_params(url);
-
-        if new_params.is_empty() {
-            self.has_visited_url_clean(Arc::new(without_query_params))
-        } else {
-            let query = new_params
-                .into_iter()
-                .fold(String::new(), |acc, (key, value)| {
-                    acc + format!("{key}={value}").as_str()
-                });
-            without_query_params.set_query(Some(query.as_str()));
-            self.has_visited_url_clean(Arc::new(without_query_params))
+        let path = normalize_percent_encoding(url.path());
+        let path = path.trim_end_matches('/');
+        canonical.set_path(if path.is_empty() { "/" } else { path });
+
+        // remove (most) query parameters from URL since they don't affect what
+        // page the URL points to. Note that some applications use query
+        // parameters to identify what page to go to, thus the below
+        // query_pairs() check. We may need to update this list as new cases
+        // are brought to light.
+        let mut params = Self::filter_query_params(url);
+        if !params.is_empty() {
+            params.sort_unstable();
+            canonical.query_pairs_mut().extend_pairs(params);
         }
+
+        canonical
     }
 
     fn filter_query_params(url: &Url) -> Vec<(Cow<'_, str>, Cow<'_, str>)> {
@@ -91,11 +214,12 @@ impl WalkCache {
     }
 
     fn has_visited_url_clean(&self, url: Arc<Url>) -> bool {
+        let counters = &self.counters;
         if self.url_cache_contains(&url) {
-            true
+            CacheCounters::record(&counters.url_hits, &counters.url_misses, true)
         } else {
             self.see_url(url);
-            false
+            CacheCounters::record(&counters.url_hits, &counters.url_misses, false)
         }
     }
 
@@ -103,9 +227,58 @@ impl WalkCache {
     pub fn clear(&mut self) {
         self.seen_scripts.clear();
         self.seen_urls.clear();
+        self.seen_integrity.clear();
+        for counter in [
+            &self.counters.url_hits,
+            &self.counters.url_misses,
+            &self.counters.script_hits,
+            &self.counters.script_misses,
+        ] {
+            counter.store(0, Ordering::Relaxed);
+        }
     }
 }
 
+fn default_port(scheme: &str) -> Option<u16> {
+    match scheme {
+        "http" | "ws" => Some(80),
+        "https" | "wss" => Some(443),
+        "ftp" => Some(21),
+        _ => None,
+    }
+}
+
+/// Decode percent-encoded unreserved characters (e.g. `%7E` -> `~`) and
+/// uppercase the hex digits of all other escapes (e.g. `%2f` -> `%2F`).
+fn normalize_percent_encoding(path: &str) -> Cow<'_, str> {
+    if !path.contains('%') {
+        return Cow::Borrowed(path);
+    }
+
+    let bytes = path.as_bytes();
+    let mut normalized = String::with_capacity(path.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        let escape = bytes.get(i + 1..i + 3).filter(|_| bytes[i] == b'%');
+        match escape.and_then(|hex| u8::from_str_radix(std::str::from_utf8(hex).ok()?, 16).ok()) {
+            Some(b) if b.is_ascii_alphanumeric() || matches!(b, b'-' | b'.' | b'_' | b'~') => {
+                normalized.push(b as char);
+                i += 3;
+            }
+            Some(b) => {
+                normalized.push_str(&format!("%{b:02X}"));
+                i += 3;
+            }
+            None => {
+                // paths are ASCII once parsed by `url`
+                normalized.push(bytes[i] as char);
+                i += 1;
+            }
+        }
+    }
+    Cow::Owned(normalized)
+}
+
 #[cfg(test)]
 mod test {
     use std::sync::Arc;
@@ -140,4 +313,96 @@ mod test {
         cache.clear();
         assert!(!cache.has_seen_script(&url));
     }
+
+    #[test]
+    fn test_canonicalize() {
+        let canonical = |url: &str| super::WalkCache::canonicalize(&Url::parse(url).unwrap());
+
+        let equivalent = [
+            ("https://example.com/foo/", "https://example.com/foo"),
+            ("https://Example.COM/foo", "https://example.com/foo"),
+            ("https://example.com:443/foo", "https://example.com/foo"),
+            ("http://example.com:80/foo", "http://example.com/foo"),
+            ("https://example.com/%7Efoo", "https://example.com/~foo"),
+            ("https://example.com/a%2fb", "https://example.com/a%2Fb"),
+            ("https://example.com/foo#section", "https://example.com/foo"),
+            (
+                "https://example.com/foo?utm_source=x",
+                "https://example.com/foo",
+            ),
+            (
+                "https://example.com/foo?page=2&id=1",
+                "https://example.com/foo/?id=1&page=2",
+            ),
+        ];
+        for (a, b) in equivalent {
+            assert_eq!(canonical(a), canonical(b), "{a} != {b}");
+        }
+
+        assert_eq!(
+            canonical("https://example.com/").as_str(),
+            "https://example.com/"
+        );
+        assert_ne!(
+            canonical("https://example.com/foo?id=1"),
+            canonical("https://example.com/foo?id=2")
+        );
+        assert_ne!(
+            canonical("https://example.com:8080/foo"),
+            canonical("https://example.com/foo")
+        );
+    }
+
+    #[test]
+    fn test_stats() {
+        let mut cache = super::WalkCache::default();
+        let url = example();
+        assert!(!cache.has_seen_url(&url));
+        assert!(cache.has_seen_url(&url));
+        assert!(!cache.see_script(Arc::clone(&url)));
+        assert!(cache.see_script(Arc::clone(&url)));
+        // looking a script up without seeing it is not counted
+        assert!(cache.has_seen_script(&url));
+
+        let stats = cache.stats();
+        assert_eq!(
+            stats,
+            super::CacheStats {
+                url_hits: 1,
+                url_misses: 1,
+                script_hits: 1,
+                script_misses: 1,
+            }
+        );
+        assert_eq!(stats.url_hit_rate(), 0.5);
+
+        // counts and scripts are shared, but seen URLs are not
+        let fresh = cache.with_fresh_urls();
+        assert!(!fresh.has_seen_url(&url));
+        assert_eq!(cache.stats().url_misses, 2);
+        assert!(fresh.see_script(Arc::clone(&url)));
+        assert_eq!(cache.stats().script_hits, 2);
+
+        cache.clear();
+        assert_eq!(cache.stats(), super::CacheStats::default());
+        assert_eq!(cache.stats().script_hit_rate(), 0.0);
+    }
+
+    #[test]
+    fn test_has_seen_url_dedup() {
+        let cache = super::WalkCache::default();
+        let urls = [
+            "https://example.com/foo",
+            "https://example.com/foo/",
+            "https://EXAMPLE.com/foo",
+            "https://example.com:443/foo",
+            "https://example.com/foo?utm_campaign=spring#top",
+        ];
+
+        let visits = urls
+            .iter()
+            .filter(|url| !cache.has_seen_url(&Arc::new(Url::parse(url).unwrap())))
+            .count();
+        assert_eq!(visits, 1);
+    }
 }
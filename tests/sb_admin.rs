@@ -1,13 +1,10 @@
+#[path = "../src/testing.rs"]
+mod testing;
+
 use keyhunter::{ApiKeyCollector, ApiKeyMessage, WebsiteWalkBuilder};
-use miette::{miette, IntoDiagnostic as _, Result};
-use std::{
-    env, ops,
-    path::{Path, PathBuf},
-    process::{self, Child, Stdio},
-    sync::mpsc,
-    thread,
-    time::Duration,
-};
+use miette::{IntoDiagnostic as _, Result};
+use std::{env, path::PathBuf, process::Stdio, sync::mpsc, thread, time::Duration};
+use testing::TestServer;
 
 /// Get the absolute path to the root of the project (where the Cargo.toml is)
 #[cfg(not(tarpaulin_include))]
@@ -44,71 +41,6 @@ fn setup_sb_admin() -> Result<PathBuf> {
     Ok(site_dir)
 }
 
-/// Start a web server at port 8080 to serve static assets located in `site_dir`
-///
-/// This function relies on npx, meaning you must have node installed on your
-/// machine for this to work.
-fn serve_local(site_dir: &Path) -> Result<AutoKilledChild> {
-    let mut serve = process::Command::new("npx");
-    serve
-        .args(["http-server", "-p", "8080"])
-        .arg(site_dir)
-        .stdout(Stdio::null());
-    serve.spawn().into_diagnostic().map(AutoKilledChild::from)
-}
-
-fn poll_server(site_url: &str) -> Result<()> {
-    const MAX_ATTEMPTS: u32 = 6;
-    let mut i = MAX_ATTEMPTS;
-
-    loop {
-        if i == 0 {
-            return Err(miette!(
-                "Server at '{}' was not ready after '{}' attempts",
-                site_url,
-                MAX_ATTEMPTS
-            ));
-        }
-        if ureq::get(site_url)
-            .timeout(Duration::from_millis(500))
-            .call()
-            .is_ok()
-        {
-            return Ok(());
-        }
-        i -= 1;
-        println!("site is not ready, retrying in 1 second...");
-        thread::sleep(Duration::from_secs(1));
-    }
-}
-
-#[derive(Debug)]
-struct AutoKilledChild(Child);
-
-impl ops::Deref for AutoKilledChild {
-    type Target = Child;
-    fn deref(&self) -> &Self::Target {
-        &self.0
-    }
-}
-
-impl ops::DerefMut for AutoKilledChild {
-    fn deref_mut(&mut self) -> &mut Self::Target {
-        &mut self.0
-    }
-}
-
-impl From<Child> for AutoKilledChild {
-    fn from(child: Child) -> Self {
-        Self(child)
-    }
-}
-impl Drop for AutoKilledChild {
-    fn drop(&mut self) {
-        self.0.kill().unwrap()
-    }
-}
-
 #[test]
 fn test_sb_admin() -> Result<()> {
     miette::set_hook(Box::new(|_| {
@@ -128,28 +60,20 @@ fn test_sb_admin() -> Result<()> {
 
     let site_dir = setup_sb_admin()?;
 
-    // Serve the dashboard site on localhost:8080
-    println!("starting server");
-    let mut serve_child = serve_local(&site_dir)?;
-    const SITE_URL: &str = "http://localhost:8080";
-
-    // wait until the server has started
-    println!("waiting for server to be ready");
-    if let Err(e) = poll_server(SITE_URL) {
-        drop(serve_child);
-        panic!("{e}");
-    }
+    // Serve the dashboard site on localhost
+    let server = TestServer::builder().dir(&site_dir).start();
+    let site_url = server.url();
 
     let builder = WebsiteWalkBuilder::new()
         .with_timeout(Duration::from_secs(1))
         .with_shared_cache(false);
 
     // first pass to test that expected # of urls were collected while walking
-    let scripts_res = builder.collect(SITE_URL);
+    let scripts_res = builder.collect(&site_url);
 
     // second pass that sends scripts to ApiKeyCollector to tests key extraction/collection
     let (key_sender, key_receiver) = mpsc::channel();
-    let (script_sender, script_receiver) = mpsc::channel();
+    let (script_sender, script_receiver) = builder.channel();
 
     let key_handle = thread::spawn(move || {
         let mut keys = vec![];
@@ -159,8 +83,8 @@ fn test_sb_admin() -> Result<()> {
                 ApiKeyMessage::Keys(api_keys) => {
                     keys.extend(api_keys);
                 }
-                ApiKeyMessage::RecoverableFailure(err) => {
-                    println!("{:?}", err);
+                ApiKeyMessage::RecoverableFailure { error, .. } => {
+                    println!("{:?}", error);
                 }
                 _ => {}
             }
@@ -175,16 +99,12 @@ fn test_sb_admin() -> Result<()> {
     });
 
     let walker = builder.build(script_sender);
-    let walk_res = walker.walk(SITE_URL);
-
-    // wait for collection to stop before terminating server. Do not check
-    // results until after server has terminated, otherwise the process will
-    // remain open.
+    let walk_res = walker.walk(&site_url);
 
+    // wait for collection to stop before stopping the server
     let collector_handle_result = collector_handle.join();
     let key_handle_result = key_handle.join();
-
-    serve_child.kill().into_diagnostic()?;
+    drop(server);
 
     let mut scripts = scripts_res?;
     collector_handle_result.unwrap();
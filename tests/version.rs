@@ -0,0 +1,29 @@
+use std::{fs, path::Path, process::Command};
+
+#[test]
+fn test_version() {
+    assert_eq!(keyhunter::version(), keyhunter::VERSION);
+    assert_eq!(keyhunter::tool_name(), "keyhunter");
+
+    // matches the version in Cargo.toml
+    let manifest =
+        fs::read_to_string(Path::new(env!("CARGO_MANIFEST_DIR")).join("Cargo.toml")).unwrap();
+    let version = manifest
+        .lines()
+        .find_map(|line| line.strip_prefix("version"))
+        .and_then(|rest| rest.trim().strip_prefix('='))
+        .map(|version| version.trim().trim_matches('"'))
+        .unwrap();
+    assert_eq!(keyhunter::version(), version);
+
+    // and the version the CLI reports
+    let output = Command::new(env!("CARGO_BIN_EXE_keyhunter"))
+        .arg("--version")
+        .output()
+        .unwrap();
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    assert_eq!(
+        stdout.trim(),
+        format!("{} {}", keyhunter::tool_name(), keyhunter::version())
+    );
+}